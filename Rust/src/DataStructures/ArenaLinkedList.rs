@@ -0,0 +1,696 @@
+use std::{fmt, iter::Rev, mem};
+
+use super::Traits::{Collection, Queue, Sequence};
+
+// Nodes live in one vector and point at each other by index, so there are
+// no reference counts to form cycles and no `RefCell`s to panic.
+struct Node<T> {
+    value: T,
+    next: Option<usize>,
+    prev: Option<usize>,
+}
+
+// Requirements
+//      1. Everything `BetterTransactionLog` does, with the same methods.
+//      2. Free every node when the log goes away.
+//      3. Never panic because a value is still borrowed.
+//      4. Move the log to another thread when its commands can go.
+
+// Access & Search are Avg O(n)
+// Insertion and Deletion are Avg O(1), splitting is O(n) in the part split
+// off, appending and splicing a log are O(n) in the log moved in, and
+// `iter_mut` and reversing make a pass over every slot. By index,
+// `split_off` and `splice` first walk to the spot from the closer end.
+
+// Positives
+//    1. Nodes sit next to each other in memory, there is less jumping
+//    around and one allocation serves many nodes.
+//    2. No cycles, the log is freed like any vector.
+//    3. Borrowing is checked by the compiler, values are plain references.
+//    4. `Send` and `Sync` whenever the commands are.
+
+// Negatives
+//    1. Removed nodes leave holes that are only reused by later inserts,
+//    the vector does not shrink until the log is empty.
+//    2. Splitting, splicing and appending have to move the nodes into
+//    another vector, none of them are O(1) like with the linked nodes.
+//    3. `iter_mut` lends out every slot up front, since the compiler cannot
+//    tell that the links never visit a node twice.
+pub struct ArenaTransactionLog<T> {
+    slots: Vec<Option<Node<T>>>,
+    // Indexes of the empty slots
+    free: Vec<usize>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    pub length: u64,
+}
+
+impl<T> ArenaTransactionLog<T> {
+    pub fn new_empty() -> Self {
+        ArenaTransactionLog {
+            slots: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            tail: None,
+            length: 0,
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.slots[index]
+            .as_ref()
+            .expect("links only point at used slots")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.slots[index]
+            .as_mut()
+            .expect("links only point at used slots")
+    }
+
+    // Links a new node in between `prev` and `next`, which have to be
+    // neighbours. `None` stands for the respective end of the log.
+    fn link_between(&mut self, prev: Option<usize>, next: Option<usize>, value: T) {
+        let node = Some(Node { value, next, prev });
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index] = node;
+                index
+            }
+            None => {
+                self.slots.push(node);
+                self.slots.len() - 1
+            }
+        };
+        match prev {
+            Some(prev) => self.node_mut(prev).next = Some(index),
+            None => self.head = Some(index),
+        }
+        match next {
+            Some(next) => self.node_mut(next).prev = Some(index),
+            None => self.tail = Some(index),
+        }
+        self.length += 1;
+    }
+
+    fn unlink(&mut self, index: usize) -> T {
+        let node = self.slots[index]
+            .take()
+            .expect("links only point at used slots");
+        match node.prev {
+            Some(prev) => self.node_mut(prev).next = node.next,
+            None => self.head = node.next,
+        }
+        match node.next {
+            Some(next) => self.node_mut(next).prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        self.length -= 1;
+        if self.length == 0 {
+            // Nothing left to keep the holes for
+            self.slots.clear();
+            self.free.clear();
+        } else {
+            self.free.push(index);
+        }
+        node.value
+    }
+
+    pub fn append_fwd(&mut self, value: T) {
+        self.link_between(None, self.head, value);
+    }
+
+    pub fn append_bwd(&mut self, value: T) {
+        self.link_between(self.tail, None, value);
+    }
+
+    pub fn pop_fwd(&mut self) -> Option<T> {
+        self.head.map(|head| self.unlink(head))
+    }
+
+    pub fn pop_bwd(&mut self) -> Option<T> {
+        self.tail.map(|tail| self.unlink(tail))
+    }
+
+    pub fn peek_fwd(&self) -> Option<&T> {
+        self.head.map(|head| &self.node(head).value)
+    }
+
+    pub fn peek_bwd(&self) -> Option<&T> {
+        self.tail.map(|tail| &self.node(tail).value)
+    }
+
+    // Walks from head to tail, leaving the log as it is. Walk backward with
+    // `.rev()`, or take from both ends at once.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            remaining: self.length as usize,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.iter_mut().map(Option::as_mut).collect(),
+            front: self.head,
+            back: self.tail,
+            remaining: self.length as usize,
+        }
+    }
+
+    pub fn iter_fwd(&self) -> Iter<'_, T> {
+        self.iter()
+    }
+
+    pub fn iter_bwd(&self) -> Rev<Iter<'_, T>> {
+        self.iter().rev()
+    }
+
+    // A cursor on the head, or on the ghost position if the log is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail,
+            index: (self.length as usize).saturating_sub(1),
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail,
+            index: (self.length as usize).saturating_sub(1),
+            list: self,
+        }
+    }
+
+    // Where a cursor goes from `current`. Past the tail there is a ghost
+    // position (`None`) that wraps around to the head.
+    fn next_of(&self, current: Option<usize>) -> Option<usize> {
+        match current {
+            Some(index) => self.node(index).next,
+            None => self.head,
+        }
+    }
+
+    fn prev_of(&self, current: Option<usize>) -> Option<usize> {
+        match current {
+            Some(index) => self.node(index).prev,
+            None => self.tail,
+        }
+    }
+
+    // Moves every command of `other` to the tail of this log, leaving
+    // `other` empty. (`append_bwd` already adds a single command.)
+    pub fn append_log(&mut self, other: &mut Self) {
+        self.extend(mem::take(other));
+    }
+
+    // A cursor on the command at `at`, or on the ghost position for the
+    // length of the log, walked to from whichever end is closer.
+    fn cursor_at(&mut self, at: usize) -> CursorMut<'_, T> {
+        let length = self.length as usize;
+        assert!(at <= length, "cannot go to {} of {}", at, length);
+        if at < length / 2 {
+            let mut cursor = self.cursor_front_mut();
+            for _ in 0..at {
+                cursor.move_next();
+            }
+            cursor
+        } else {
+            let mut cursor = self.cursor_back_mut();
+            if at == length {
+                cursor.move_next();
+            }
+            for _ in at + 1..length {
+                cursor.move_prev();
+            }
+            cursor
+        }
+    }
+
+    // Cuts the log in two, keeping the first `at` commands and handing back
+    // the rest.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let length = self.length as usize;
+        assert!(at <= length, "cannot split off at {} of {}", at, length);
+        match at {
+            0 => mem::take(self),
+            _ => self.cursor_at(at - 1).split_after(),
+        }
+    }
+
+    // Moves every command of `other` in before the command at `at`,
+    // leaving `other` empty.
+    pub fn splice(&mut self, at: usize, other: &mut Self) {
+        self.cursor_at(at).splice_before(other);
+    }
+
+    // Turns the log around in place by swapping every node's links.
+    pub fn reverse(&mut self) {
+        for node in self.slots.iter_mut().flatten() {
+            mem::swap(&mut node.next, &mut node.prev);
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+
+    // Moves the nodes from `first` up to `stop` into a new log of their
+    // own, in one pass. The caller relinks what is left around them.
+    fn take_chain(&mut self, first: Option<usize>, stop: Option<usize>) -> ArenaTransactionLog<T> {
+        let mut rest = ArenaTransactionLog::new_empty();
+        let mut current = first;
+        while current != stop {
+            let Some(index) = current else { break };
+            let node = self.slots[index]
+                .take()
+                .expect("links only point at used slots");
+            self.free.push(index);
+            current = node.next;
+            let moved = rest.slots.len();
+            rest.slots.push(Some(Node {
+                value: node.value,
+                next: Some(moved + 1).filter(|_| current != stop),
+                prev: moved.checked_sub(1),
+            }));
+        }
+        let length = rest.slots.len();
+        if length > 0 {
+            rest.head = Some(0);
+            rest.tail = Some(length - 1);
+        }
+        rest.length = length as u64;
+        self.length -= rest.length;
+        rest
+    }
+}
+
+impl<T: Clone> Clone for ArenaTransactionLog<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Default for ArenaTransactionLog<T> {
+    fn default() -> Self {
+        ArenaTransactionLog::new_empty()
+    }
+}
+
+impl<T> Collection for ArenaTransactionLog<T> {
+    fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    fn clear(&mut self) {
+        *self = ArenaTransactionLog::new_empty();
+    }
+}
+
+// Pushes at the tail and pops from the head, like `append_bwd` and
+// `pop_fwd`.
+impl<T: Clone> Sequence for ArenaTransactionLog<T> {
+    type Item = T;
+
+    fn push(&mut self, item: T) {
+        self.append_bwd(item);
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.iter().nth(index).cloned()
+    }
+}
+
+impl<T: Clone> Queue for ArenaTransactionLog<T> {
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_fwd()
+    }
+}
+
+// Lists the values from head to tail.
+impl<T: fmt::Debug> fmt::Debug for ArenaTransactionLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for ArenaTransactionLog<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut log = ArenaTransactionLog::new_empty();
+        log.extend(iter);
+        log
+    }
+}
+
+// Appends at the tail, like `append_bwd`.
+impl<T> Extend<T> for ArenaTransactionLog<T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        for value in iter {
+            self.append_bwd(value);
+        }
+    }
+}
+
+// Moves through the log in both directions without changing it.
+pub struct Cursor<'a, T> {
+    list: &'a ArenaTransactionLog<T>,
+    current: Option<usize>,
+    // Position of `current` in the log, meaningless on the ghost
+    index: usize,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    // Index of the current value, `None` on the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        self.current = self.list.next_of(self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index.wrapping_sub(1),
+            None => (self.list.length as usize).wrapping_sub(1),
+        };
+        self.current = self.list.prev_of(self.current);
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        self.current.map(|index| &self.list.node(index).value)
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = self.list.next_of(self.current);
+        next.map(|index| &self.list.node(index).value)
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = self.list.prev_of(self.current);
+        prev.map(|index| &self.list.node(index).value)
+    }
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Cursor {
+            list: self.list,
+            current: self.current,
+            index: self.index,
+        }
+    }
+}
+
+// A cursor that can also insert and remove values where it points.
+pub struct CursorMut<'a, T> {
+    list: &'a mut ArenaTransactionLog<T>,
+    current: Option<usize>,
+    index: usize,
+}
+
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.as_cursor().index()
+    }
+
+    pub fn move_next(&mut self) {
+        let mut cursor = self.as_cursor();
+        cursor.move_next();
+        (self.current, self.index) = (cursor.current, cursor.index);
+    }
+
+    pub fn move_prev(&mut self) {
+        let mut cursor = self.as_cursor();
+        cursor.move_prev();
+        (self.current, self.index) = (cursor.current, cursor.index);
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current
+            .map(|index| &mut self.list.node_mut(index).value)
+    }
+
+    pub fn peek_next(&self) -> Option<&T> {
+        self.as_cursor().peek_next()
+    }
+
+    pub fn peek_prev(&self) -> Option<&T> {
+        self.as_cursor().peek_prev()
+    }
+
+    // A read-only cursor at the same position.
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self.list,
+            current: self.current,
+            index: self.index,
+        }
+    }
+
+    // Inserts `value` after the current one, or at the head when on the
+    // ghost position.
+    pub fn insert_after(&mut self, value: T) {
+        let next = self.list.next_of(self.current);
+        self.list.link_between(self.current, next, value);
+    }
+
+    // Inserts `value` before the current one, or at the tail when on the
+    // ghost position.
+    pub fn insert_before(&mut self, value: T) {
+        let prev = self.list.prev_of(self.current);
+        self.list.link_between(prev, self.current, value);
+        if self.current.is_some() {
+            self.index += 1;
+        }
+    }
+
+    // Moves every value of `other` in after the current one, or to the
+    // head when on the ghost position.
+    pub fn splice_after(&mut self, other: &mut ArenaTransactionLog<T>) {
+        for value in mem::take(other).into_iter().rev() {
+            self.insert_after(value);
+        }
+    }
+
+    // Moves every value of `other` in before the current one, or to the
+    // tail when on the ghost position.
+    pub fn splice_before(&mut self, other: &mut ArenaTransactionLog<T>) {
+        for value in mem::take(other) {
+            self.insert_before(value);
+        }
+    }
+
+    // Takes the current value out and moves on to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current?;
+        self.current = self.list.node(current).next;
+        Some(self.list.unlink(current))
+    }
+
+    // Splits off everything after the current value. On the ghost position
+    // that is the whole log.
+    pub fn split_after(&mut self) -> ArenaTransactionLog<T> {
+        let Some(current) = self.current else {
+            return mem::take(self.list);
+        };
+        let first = self.list.node_mut(current).next.take();
+        self.list.tail = Some(current);
+        self.list.take_chain(first, None)
+    }
+
+    // Splits off everything before the current value, which becomes the
+    // head. On the ghost position that is the whole log.
+    pub fn split_before(&mut self) -> ArenaTransactionLog<T> {
+        let Some(current) = self.current else {
+            return mem::take(self.list);
+        };
+        self.list.node_mut(current).prev = None;
+        let first = self.list.head.replace(current);
+        self.index = 0;
+        self.list.take_chain(first, Some(current))
+    }
+}
+
+// Takes values from the front and the back until the two ends meet.
+pub struct Iter<'a, T> {
+    list: &'a ArenaTransactionLog<T>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.front?);
+        self.remaining -= 1;
+        self.front = node.next;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.back?);
+        self.remaining -= 1;
+        self.back = node.prev;
+        Some(&node.value)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            list: self.list,
+            front: self.front,
+            back: self.back,
+            remaining: self.remaining,
+        }
+    }
+}
+
+// Every slot is lent out up front, and the links pick the values out of
+// them one by one, from either end.
+pub struct IterMut<'a, T> {
+    slots: Vec<Option<&'a mut Node<T>>>,
+    front: Option<usize>,
+    back: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.slots[self.front?].take()?;
+        self.remaining -= 1;
+        self.front = node.next;
+        Some(&mut node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.slots[self.back?].take()?;
+        self.remaining -= 1;
+        self.back = node.prev;
+        Some(&mut node.value)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Pops the values off either end.
+pub struct IntoIter<T>(ArenaTransactionLog<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_fwd()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.0.length as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_bwd()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for ArenaTransactionLog<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ArenaTransactionLog<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut ArenaTransactionLog<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Logs are written as a plain sequence of commands from head to tail.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::ArenaTransactionLog;
+
+    impl<T: Serialize> Serialize for ArenaTransactionLog<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for ArenaTransactionLog<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+        }
+    }
+}
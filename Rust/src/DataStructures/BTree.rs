@@ -0,0 +1,390 @@
+// Requirements
+//    1. Store a large number of IoT device objects.
+//    2. Retrieve IoT objects by numerical name.
+//    3. Iterate over IoT objects, in full or over a range of names.
+//    4. Remove IoT objects.
+
+// Access, Search, Insertion and Deletion are Avg O(log(n))
+// Access, Search, Insertion and Deletion are Worst O(log(n))
+
+// Positives
+//    1. High fanout keeps the tree shallow, so there is little pointer
+//    chasing per lookup.
+//    2. Devices in a node sit next to each other in memory, which is
+//    cache (and disk) friendly.
+//    3. The tree is always perfectly balanced.
+//    4. The order can be tuned to the size of a cache line or page.
+
+// Negatives
+//    1. Implementation complexity, especially for deletion.
+//    2. Inserts and deletes shift devices around within a node.
+//    3. Nodes are only guaranteed to be half full.
+use std::{
+    fmt, mem,
+    ops::{Bound, RangeBounds},
+};
+
+use super::{
+    BinarySearchTree::IoTDevice,
+    Traits::{Collection, OrderedMap},
+};
+
+// Order used by `default()`
+const DEFAULT_ORDER: usize = 8;
+
+type Tree<I, A> = Box<Node<I, A>>;
+// Median device and new right sibling of a node that overflowed
+type Split<I, A> = Option<(IoTDevice<I, A>, Tree<I, A>)>;
+struct Node<I, A> {
+    devices: Vec<IoTDevice<I, A>>,
+    children: Vec<Tree<I, A>>,
+}
+
+impl<I, A> Node<I, A> {
+    fn new_leaf() -> Tree<I, A> {
+        Box::new(Node {
+            devices: Vec::new(),
+            children: Vec::new(),
+        })
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+pub struct DeviceDatabase<I, A> {
+    root: Tree<I, A>,
+    order: usize,
+    pub length: usize,
+}
+
+impl<I: PartialOrd + Clone, A: Clone> DeviceDatabase<I, A> {
+    // `order` is the maximum number of children per node (at least 3).
+    pub fn new_empty(order: usize) -> DeviceDatabase<I, A> {
+        assert!(order >= 3, "a B-Tree needs an order of at least 3");
+        DeviceDatabase {
+            root: Node::new_leaf(),
+            order,
+            length: 0,
+        }
+    }
+
+    fn max_devices(&self) -> usize {
+        self.order - 1
+    }
+
+    fn min_devices(&self) -> usize {
+        self.order.div_ceil(2) - 1
+    }
+
+    // Adds a device, replacing (and returning) one with the same id.
+    pub fn add(&mut self, device: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        let mut root = mem::replace(&mut self.root, Node::new_leaf());
+        let (previous, split) = self.insert_into(&mut root, device);
+        self.root = match split {
+            // The root was full, so the tree grows by one level
+            Some((median, right)) => Box::new(Node {
+                devices: vec![median],
+                children: vec![root, right],
+            }),
+            None => root,
+        };
+        if previous.is_none() {
+            self.length += 1;
+        }
+        previous
+    }
+
+    // Inserts below `node` and hands back the median and new right sibling
+    // if `node` had to be split.
+    fn insert_into(
+        &self,
+        node: &mut Node<I, A>,
+        device: IoTDevice<I, A>,
+    ) -> (Option<IoTDevice<I, A>>, Split<I, A>) {
+        let pos = node.devices.partition_point(|d| d.id < device.id);
+        if pos < node.devices.len() && node.devices[pos].id == device.id {
+            let previous = mem::replace(&mut node.devices[pos], device);
+            return (Some(previous), None);
+        }
+
+        if node.is_leaf() {
+            node.devices.insert(pos, device);
+        } else {
+            let (previous, split) = self.insert_into(&mut node.children[pos], device);
+            if previous.is_some() {
+                return (previous, None);
+            }
+            if let Some((median, right)) = split {
+                node.devices.insert(pos, median);
+                node.children.insert(pos + 1, right);
+            }
+        }
+
+        if node.devices.len() > self.max_devices() {
+            (None, Some(self.split(node)))
+        } else {
+            (None, None)
+        }
+    }
+
+    fn split(&self, node: &mut Node<I, A>) -> (IoTDevice<I, A>, Tree<I, A>) {
+        let mid = node.devices.len() / 2;
+        let right_devices = node.devices.split_off(mid + 1);
+        let median = node.devices.pop().expect("split of an empty node");
+        let right_children = if node.is_leaf() {
+            Vec::new()
+        } else {
+            node.children.split_off(mid + 1)
+        };
+        let right = Box::new(Node {
+            devices: right_devices,
+            children: right_children,
+        });
+        (median, right)
+    }
+
+    pub fn find(&self, id: I) -> Option<IoTDevice<I, A>> {
+        self.find_r(&self.root, id)
+    }
+
+    fn find_r(&self, node: &Node<I, A>, id: I) -> Option<IoTDevice<I, A>> {
+        let pos = node.devices.partition_point(|d| d.id < id);
+        if pos < node.devices.len() && node.devices[pos].id == id {
+            Some(node.devices[pos].clone())
+        } else if node.is_leaf() {
+            None
+        } else {
+            self.find_r(&node.children[pos], id)
+        }
+    }
+
+    pub fn remove(&mut self, id: I) -> Option<IoTDevice<I, A>> {
+        let mut root = mem::replace(&mut self.root, Node::new_leaf());
+        let removed = self.remove_rec(&mut root, &id);
+        // An emptied root hands over to its only child, shrinking the tree
+        if root.devices.is_empty() && !root.is_leaf() {
+            root = root.children.remove(0);
+        }
+        self.root = root;
+        if removed.is_some() {
+            self.length -= 1;
+        }
+        removed
+    }
+
+    fn remove_rec(&self, node: &mut Node<I, A>, id: &I) -> Option<IoTDevice<I, A>> {
+        let pos = node.devices.partition_point(|d| d.id < *id);
+        let found = pos < node.devices.len() && node.devices[pos].id == *id;
+
+        if node.is_leaf() {
+            return if found {
+                Some(node.devices.remove(pos))
+            } else {
+                None
+            };
+        }
+
+        let removed = if found {
+            // Swap in the in-order predecessor, the largest device on the left
+            let predecessor = self.remove_max(&mut node.children[pos]);
+            Some(mem::replace(&mut node.devices[pos], predecessor))
+        } else {
+            self.remove_rec(&mut node.children[pos], id)
+        };
+        if node.children[pos].devices.len() < self.min_devices() {
+            self.fix_underflow(node, pos);
+        }
+        removed
+    }
+
+    fn remove_max(&self, node: &mut Node<I, A>) -> IoTDevice<I, A> {
+        if node.is_leaf() {
+            return node.devices.pop().expect("remove_max of an empty node");
+        }
+        let last = node.children.len() - 1;
+        let max = self.remove_max(&mut node.children[last]);
+        if node.children[last].devices.len() < self.min_devices() {
+            self.fix_underflow(node, last);
+        }
+        max
+    }
+
+    // Refills `node.children[pos]` by borrowing from a sibling that can spare
+    // a device, or merges it with a sibling otherwise.
+    fn fix_underflow(&self, node: &mut Node<I, A>, pos: usize) {
+        if pos > 0 && node.children[pos - 1].devices.len() > self.min_devices() {
+            let (left, right) = node.children.split_at_mut(pos);
+            let (left, child) = (&mut left[pos - 1], &mut right[0]);
+            let borrowed = left.devices.pop().unwrap();
+            let separator = mem::replace(&mut node.devices[pos - 1], borrowed);
+            child.devices.insert(0, separator);
+            if let Some(c) = left.children.pop() {
+                child.children.insert(0, c);
+            }
+        } else if pos + 1 < node.children.len()
+            && node.children[pos + 1].devices.len() > self.min_devices()
+        {
+            let (left, right) = node.children.split_at_mut(pos + 1);
+            let (child, right) = (&mut left[pos], &mut right[0]);
+            let borrowed = right.devices.remove(0);
+            let separator = mem::replace(&mut node.devices[pos], borrowed);
+            child.devices.push(separator);
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else if pos > 0 {
+            self.merge(node, pos - 1);
+        } else {
+            self.merge(node, pos);
+        }
+    }
+
+    // Folds `children[pos + 1]` and the separator between them into
+    // `children[pos]`.
+    fn merge(&self, node: &mut Node<I, A>, pos: usize) {
+        let right = node.children.remove(pos + 1);
+        let separator = node.devices.remove(pos);
+        let left = &mut node.children[pos];
+        left.devices.push(separator);
+        left.devices.extend(right.devices);
+        left.children.extend(right.children);
+    }
+
+    // Visits devices in ascending id order.
+    pub fn walk(&self, callback: impl Fn(&IoTDevice<I, A>)) {
+        self.walk_in_order(&self.root, &callback)
+    }
+
+    fn walk_in_order(&self, node: &Node<I, A>, callback: &impl Fn(&IoTDevice<I, A>)) {
+        for (i, device) in node.devices.iter().enumerate() {
+            if !node.is_leaf() {
+                self.walk_in_order(&node.children[i], callback);
+            }
+            callback(device);
+        }
+        if let Some(last) = node.children.last() {
+            self.walk_in_order(last, callback);
+        }
+    }
+
+    // Collects the devices whose ids fall into `range`, in ascending order.
+    // Only subtrees that can overlap the range are visited.
+    pub fn range(&self, range: impl RangeBounds<I>) -> Vec<IoTDevice<I, A>> {
+        let mut result = Vec::new();
+        self.range_rec(&self.root, &range, &mut result);
+        result
+    }
+
+    fn range_rec(
+        &self,
+        node: &Node<I, A>,
+        range: &impl RangeBounds<I>,
+        result: &mut Vec<IoTDevice<I, A>>,
+    ) {
+        let start = match range.start_bound() {
+            Bound::Included(s) | Bound::Excluded(s) => node.devices.partition_point(|d| d.id < *s),
+            Bound::Unbounded => 0,
+        };
+        for i in start..=node.devices.len() {
+            if !node.is_leaf() {
+                self.range_rec(&node.children[i], range, result);
+            }
+            match node.devices.get(i) {
+                Some(device) => {
+                    let past_end = match range.end_bound() {
+                        Bound::Included(e) => device.id > *e,
+                        Bound::Excluded(e) => device.id >= *e,
+                        Bound::Unbounded => false,
+                    };
+                    if past_end {
+                        return;
+                    }
+                    if range.contains(&device.id) {
+                        result.push(device.clone());
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Default for DeviceDatabase<I, A> {
+    fn default() -> Self {
+        DeviceDatabase::new_empty(DEFAULT_ORDER)
+    }
+}
+
+impl<I, A> Collection for DeviceDatabase<I, A> {
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn clear(&mut self) {
+        self.root = Node::new_leaf();
+        self.length = 0;
+    }
+}
+
+// Devices are stored under their id, so `insert` overwrites the device's
+// id with the key it is given. A device with id 9 inserted under key 5 is
+// found under 5 from then on, and has id 5.
+impl<I: PartialOrd + Clone, A: Clone> OrderedMap for DeviceDatabase<I, A> {
+    type Key = I;
+    type Value = IoTDevice<I, A>;
+
+    fn insert(&mut self, key: I, mut value: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        value.id = key;
+        self.add(value)
+    }
+
+    fn get(&self, key: &I) -> Option<IoTDevice<I, A>> {
+        self.find(key.clone())
+    }
+
+    fn keys(&self) -> Vec<I> {
+        self.range(..).into_iter().map(|d| d.id).collect()
+    }
+}
+
+// Lists the devices in ascending id order.
+impl<I: fmt::Debug, A: fmt::Debug> fmt::Debug for DeviceDatabase<I, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn entries<I: fmt::Debug, A: fmt::Debug>(
+            node: &Node<I, A>,
+            list: &mut fmt::DebugList<'_, '_>,
+        ) {
+            for (i, device) in node.devices.iter().enumerate() {
+                if let Some(child) = node.children.get(i) {
+                    entries(child, list);
+                }
+                list.entry(device);
+            }
+            if let Some(last) = node.children.get(node.devices.len()) {
+                entries(last, list);
+            }
+        }
+        let mut list = f.debug_list();
+        entries(&self.root, &mut list);
+        list.finish()
+    }
+}
+
+// Uses the default order; later devices replace earlier ones with the same id.
+impl<I: PartialOrd + Clone, A: Clone> FromIterator<IoTDevice<I, A>> for DeviceDatabase<I, A> {
+    fn from_iter<It: IntoIterator<Item = IoTDevice<I, A>>>(iter: It) -> Self {
+        let mut database = DeviceDatabase::default();
+        database.extend(iter);
+        database
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Extend<IoTDevice<I, A>> for DeviceDatabase<I, A> {
+    fn extend<It: IntoIterator<Item = IoTDevice<I, A>>>(&mut self, iter: It) {
+        for device in iter {
+            self.add(device);
+        }
+    }
+}
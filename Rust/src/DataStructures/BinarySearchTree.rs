@@ -11,15 +11,28 @@
 //    2. Efficient and fast search.
 //    3. Traversal allows for different orderings.
 //    4. Great for large amounts of unsorted data.
+//    5. Unbalanced trees can be repaired in linear time.
 
 // Negatives
 //    1. Worst-case performance is that of a linked list.
 //    2. Unbalanced trees are easy to create by accident.
-//    3. Unbalanced trees cannot be repaired.
-//    4. Recursive algorithms can overflow on unbalanced trees.
-use std::mem;
+//    3. Recursive algorithms can overflow on unbalanced trees.
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt::{self, Debug, Write},
+    mem,
+    net::{AddrParseError, IpAddr},
+    ops::{Bound, RangeBounds},
+    str::FromStr,
+};
+
+use super::Traits::{Collection, OrderedMap};
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IoTDevice<I, A> {
   pub id: I,
   pub address: A,
@@ -42,9 +55,132 @@ impl<I: PartialEq, A: PartialEq> PartialEq for IoTDevice<I, A> {
   }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceKind {
+    Sensor,
+    Actuator,
+    Gateway,
+    Camera,
+    Unknown,
+}
+
+impl FromStr for DeviceKind {
+    type Err = DeviceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sensor" => Ok(DeviceKind::Sensor),
+            "actuator" => Ok(DeviceKind::Actuator),
+            "gateway" => Ok(DeviceKind::Gateway),
+            "camera" => Ok(DeviceKind::Camera),
+            "unknown" => Ok(DeviceKind::Unknown),
+            _ => Err(DeviceError::InvalidKind(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DeviceKind::Sensor => "sensor",
+            DeviceKind::Actuator => "actuator",
+            DeviceKind::Gateway => "gateway",
+            DeviceKind::Camera => "camera",
+            DeviceKind::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeviceError {
+    InvalidAddress(String, AddrParseError),
+    InvalidKind(String),
+    EmptyPath,
+}
+
+impl fmt::Display for DeviceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceError::InvalidAddress(address, e) => {
+                write!(f, "invalid IP address {:?}: {}", address, e)
+            }
+            DeviceError::InvalidKind(kind) => write!(f, "unknown device kind {:?}", kind),
+            DeviceError::EmptyPath => write!(f, "device path is empty"),
+        }
+    }
+}
+
+impl Error for DeviceError {}
+
+// An `IoTDevice` whose address is a validated IP and that knows its kind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedIoTDevice<I> {
+    pub id: I,
+    pub address: IpAddr,
+    pub path: String,
+    pub kind: DeviceKind,
+}
+
+impl<I> TypedIoTDevice<I> {
+    pub fn new(
+        id: I,
+        address: &str,
+        path: impl Into<String>,
+        kind: DeviceKind,
+    ) -> Result<TypedIoTDevice<I>, DeviceError> {
+        let path = path.into();
+        if path.is_empty() {
+            return Err(DeviceError::EmptyPath);
+        }
+        Ok(TypedIoTDevice {
+            id,
+            address: parse_address(address)?,
+            path,
+            kind,
+        })
+    }
+
+    // Validates a generic device, which carries no kind of its own.
+    pub fn from_device<A: AsRef<str>>(
+        device: IoTDevice<I, A>,
+        kind: DeviceKind,
+    ) -> Result<TypedIoTDevice<I>, DeviceError> {
+        TypedIoTDevice::new(
+            device.id,
+            device.address.as_ref(),
+            device.path.as_ref(),
+            kind,
+        )
+    }
+}
+
+impl<I, A: AsRef<str>> TryFrom<IoTDevice<I, A>> for TypedIoTDevice<I> {
+    type Error = DeviceError;
+
+    fn try_from(device: IoTDevice<I, A>) -> Result<Self, Self::Error> {
+        TypedIoTDevice::from_device(device, DeviceKind::Unknown)
+    }
+}
+
+impl<I> From<TypedIoTDevice<I>> for IoTDevice<I, String> {
+    fn from(device: TypedIoTDevice<I>) -> Self {
+        IoTDevice::new(device.id, device.address.to_string(), device.path)
+    }
+}
+
+pub(crate) fn parse_address(address: &str) -> Result<IpAddr, DeviceError> {
+    address
+        .trim()
+        .parse()
+        .map_err(|e| DeviceError::InvalidAddress(address.to_string(), e))
+}
+
 type Tree<I, A> = Option<Box<Node<I, A>>>;
 struct Node<I, A> {
   pub dev: IoTDevice<I, A>,
+  // Number of devices in the subtree rooted here, for order statistics
+  size: usize,
   left: Tree<I, A>,
   right: Tree<I, A>,
 }
@@ -53,14 +189,108 @@ impl<I, A> Node<I, A> {
   fn new(dev: IoTDevice<I, A>) -> Tree<I, A> {
     Some(Box::new(Node {
       dev,
+      size: 1,
       left: None,
       right: None,
     }))
   }
+
+  fn size(tree: &Tree<I, A>) -> usize {
+    tree.as_ref().map_or(0, |n| n.size)
+  }
+
+  fn update_size(&mut self) {
+    self.size = 1 + Node::size(&self.left) + Node::size(&self.right);
+  }
+
+  // Links the next `count` nodes (given in in-order) into a perfectly
+  // balanced tree. Recursion only goes log(count) deep.
+  fn build(nodes: &mut impl Iterator<Item = Box<Node<I, A>>>, count: usize) -> Tree<I, A> {
+    if count == 0 {
+      return None;
+    }
+    let left_count = count / 2;
+    let left = Node::build(nodes, left_count);
+    let mut node = nodes.next().expect("fewer nodes than counted");
+    node.left = left;
+    node.right = Node::build(nodes, count - left_count - 1);
+    node.update_size();
+    Some(node)
+  }
+}
+
+// Secondary lookups by address and by path, enabled on demand. A device
+// handed out as `&mut` may have its keys changed, so it is taken out of the
+// maps when handed out and put back by the next call that takes the
+// registry as `&mut`. Until then lookups check it directly.
+struct Indexes<I, A> {
+    by_address: BTreeMap<A, Vec<I>>,
+    by_path: BTreeMap<A, Vec<I>>,
+    // Ids handed out mutably, which are missing from the maps
+    pending: Vec<I>,
+    // Every device was handed out mutably and the maps are empty
+    stale: bool,
+    // `add` and `remove`, which need `A: Ord`. The registry's own methods
+    // only ask for `A: Clone`, so they go through these, picked when the
+    // indexes are enabled.
+    index: fn(&mut Indexes<I, A>, &IoTDevice<I, A>),
+    unindex: fn(&mut Indexes<I, A>, &IoTDevice<I, A>),
+}
+
+impl<I, A> Indexes<I, A> {
+    fn clear(&mut self) {
+        self.by_address.clear();
+        self.by_path.clear();
+        self.pending.clear();
+        self.stale = false;
+    }
+}
+
+impl<I: PartialEq + Clone, A: Ord + Clone> Indexes<I, A> {
+    fn new() -> Indexes<I, A> {
+        Indexes {
+            by_address: BTreeMap::new(),
+            by_path: BTreeMap::new(),
+            pending: Vec::new(),
+            stale: false,
+            index: Indexes::add,
+            unindex: Indexes::remove,
+        }
+    }
+
+    fn add(&mut self, device: &IoTDevice<I, A>) {
+        let id = &device.id;
+        self.by_address
+            .entry(device.address.clone())
+            .or_default()
+            .push(id.clone());
+        self.by_path
+            .entry(device.path.clone())
+            .or_default()
+            .push(id.clone());
+    }
+
+    fn remove(&mut self, device: &IoTDevice<I, A>) {
+        let id = &device.id;
+        for (map, key) in [
+            (&mut self.by_address, &device.address),
+            (&mut self.by_path, &device.path),
+        ] {
+            if let Some(ids) = map.get_mut(key) {
+                if let Some(pos) = ids.iter().position(|i| i == id) {
+                    ids.swap_remove(pos);
+                }
+                if ids.is_empty() {
+                    map.remove(key);
+                }
+            }
+        }
+    }
 }
 
 pub struct DeviceRegistry<I, A> {
   root: Tree<I, A>,
+    indexes: Option<Indexes<I, A>>,
     pub length: usize,
 }
 
@@ -68,19 +298,121 @@ impl<I: PartialOrd + Clone, A: Clone> DeviceRegistry<I, A> {
     pub fn new_empty() -> DeviceRegistry<I, A> {
         DeviceRegistry {
             root: None,
+            indexes: None,
             length: 0,
         }
     }
 
+    // Builds a perfectly balanced registry from devices sorted by strictly
+    // ascending id. Anything else is sorted first, and of several devices
+    // with the same id the last one is kept, like `add` would.
+    pub fn from_sorted(mut devices: Vec<IoTDevice<I, A>>) -> DeviceRegistry<I, A> {
+        if !devices.windows(2).all(|w| w[0].id < w[1].id) {
+            // A stable sort keeps devices with the same id in input order
+            devices.sort_by(|a, b| a.id.partial_cmp(&b.id).unwrap_or(Ordering::Equal));
+            let mut unique: Vec<IoTDevice<I, A>> = Vec::with_capacity(devices.len());
+            for device in devices {
+                match unique.last_mut() {
+                    Some(last) if last.id == device.id => *last = device,
+                    _ => unique.push(device),
+                }
+            }
+            devices = unique;
+        }
+        let length = devices.len();
+        // The tree's in-order runs from the largest id to the smallest
+        let mut nodes = devices.into_iter().rev().flat_map(Node::new);
+        DeviceRegistry {
+            root: Node::build(&mut nodes, length),
+            indexes: None,
+            length,
+        }
+    }
+
+    // Restores minimal height in O(n), relinking the existing nodes instead
+    // of moving any devices.
+    pub fn rebalance(&mut self) {
+        let mut nodes = Vec::with_capacity(self.length);
+        let mut stack = Vec::new();
+        let mut current = self.root.take();
+        loop {
+            while let Some(mut n) = current {
+                current = n.left.take();
+                stack.push(n);
+            }
+            match stack.pop() {
+                Some(mut n) => {
+                    current = n.right.take();
+                    nodes.push(n);
+                }
+                None => break,
+            }
+        }
+        let count = nodes.len();
+        self.root = Node::build(&mut nodes.into_iter(), count);
+    }
+
+    // Registers a device, replacing any device with the same id.
     pub fn add(&mut self, device: IoTDevice<I, A>) {
+        self.insert(device);
+    }
+
+    // Registers a device and returns the one it replaced, if the id was taken.
+    pub fn insert(&mut self, device: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        self.sync_indexes();
+        if let Some(indexes) = self.indexes.as_mut() {
+            (indexes.index)(indexes, &device);
+        }
+        if let Some(existing) = self.find_mut(&device.id) {
+            let previous = mem::replace(existing, device);
+            if let Some(indexes) = self.indexes.as_mut() {
+                (indexes.unindex)(indexes, &previous);
+            }
+            return Some(previous);
+        }
         self.length += 1;
-        let root = mem::replace(&mut self.root, None);
+        let root = self.root.take();
         self.root = self.add_rec(root, device);
+        None
+    }
+
+    // Registers a device only if its id is free, handing it back otherwise.
+    pub fn try_insert(&mut self, device: IoTDevice<I, A>) -> Result<(), IoTDevice<I, A>> {
+        if self.lookup(&device.id).is_some() {
+            return Err(device);
+        }
+        self.insert(device);
+        Ok(())
+    }
+
+    // Like `insert`, but rejects devices whose address isn't a valid IP or
+    // whose path is empty before they reach the registry.
+    pub fn insert_checked(
+        &mut self,
+        device: IoTDevice<I, A>,
+    ) -> Result<Option<IoTDevice<I, A>>, DeviceError>
+    where
+        A: AsRef<str>,
+    {
+        parse_address(device.address.as_ref())?;
+        if device.path.as_ref().is_empty() {
+            return Err(DeviceError::EmptyPath);
+        }
+        Ok(self.insert(device))
+    }
+
+    pub fn entry(&mut self, id: I) -> Entry<'_, I, A> {
+        if self.lookup(&id).is_some() {
+            Entry::Occupied(OccupiedEntry { registry: self, id })
+        } else {
+            Entry::Vacant(VacantEntry { registry: self, id })
+        }
     }
 
     fn add_rec(&self, node: Tree<I, A>, device: IoTDevice<I, A>) -> Tree<I, A> {
         match node {
             Some(mut n) => {
+                n.size += 1;
                 if n.dev.id <= device.id {
                     n.left = self.add_rec(n.left, device);
                 } else {
@@ -111,15 +443,898 @@ impl<I: PartialOrd + Clone, A: Clone> DeviceRegistry<I, A> {
         }
     }
 
-    pub fn walk(&self, callback: impl Fn(&IoTDevice<I, A>) -> ()) {
-        self.walk_in_order(&self.root, &callback)
+    fn lookup(&self, id: &I) -> Option<&IoTDevice<I, A>> {
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            if n.dev.id == *id {
+                return Some(&n.dev);
+            }
+            node = if n.dev.id < *id {
+                n.left.as_deref()
+            } else {
+                n.right.as_deref()
+            };
+        }
+        None
+    }
+
+    fn find_mut(&mut self, id: &I) -> Option<&mut IoTDevice<I, A>> {
+        let mut node = self.root.as_deref_mut();
+        while let Some(n) = node {
+            if n.dev.id == *id {
+                return Some(&mut n.dev);
+            }
+            node = if n.dev.id < *id {
+                n.left.as_deref_mut()
+            } else {
+                n.right.as_deref_mut()
+            };
+        }
+        None
+    }
+
+    pub fn remove(&mut self, id: I) -> Option<IoTDevice<I, A>> {
+        self.sync_indexes();
+        let root = self.root.take();
+        let (root, removed) = self.remove_rec(root, id);
+        self.root = root;
+        if let Some(device) = &removed {
+            self.length -= 1;
+            if let Some(indexes) = self.indexes.as_mut() {
+                (indexes.unindex)(indexes, device);
+            }
+        }
+        removed
+    }
+
+    // Puts devices that were handed out as `&mut` back into the indexes.
+    fn sync_indexes(&mut self) {
+        let Some(mut indexes) = self.indexes.take() else {
+            return;
+        };
+        if indexes.stale {
+            indexes.stale = false;
+            for device in self.iter() {
+                (indexes.index)(&mut indexes, device);
+            }
+        } else {
+            for id in mem::take(&mut indexes.pending) {
+                if let Some(device) = self.lookup(&id) {
+                    (indexes.index)(&mut indexes, device);
+                }
+            }
+        }
+        self.indexes = Some(indexes);
+    }
+
+    // `find_mut` for devices handed out to callers, who may change their keys.
+    fn find_mut_tracked(&mut self, id: &I) -> Option<&mut IoTDevice<I, A>> {
+        if let Some(mut indexes) = self.indexes.take() {
+            if !indexes.stale && !indexes.pending.contains(id) {
+                if let Some(device) = self.lookup(id) {
+                    (indexes.unindex)(&mut indexes, device);
+                    indexes.pending.push(id.clone());
+                }
+            }
+            self.indexes = Some(indexes);
+        }
+        self.find_mut(id)
+    }
+
+    fn remove_rec(&self, node: Tree<I, A>, id: I) -> (Tree<I, A>, Option<IoTDevice<I, A>>) {
+        match node {
+            Some(mut n) => {
+                if n.dev.id == id {
+                    match (n.left.take(), n.right.take()) {
+                        (None, None) => (None, Some(n.dev)),
+                        (Some(child), None) | (None, Some(child)) => (Some(child), Some(n.dev)),
+                        (Some(left), Some(right)) => {
+                            // Replace the device with its in-order successor
+                            let (right, successor) = self.remove_leftmost(right);
+                            n.left = Some(left);
+                            n.right = right;
+                            n.update_size();
+                            let removed = mem::replace(&mut n.dev, successor);
+                            (Some(n), Some(removed))
+                        }
+                    }
+                } else if n.dev.id < id {
+                    let (left, removed) = self.remove_rec(n.left.take(), id);
+                    n.left = left;
+                    n.update_size();
+                    (Some(n), removed)
+                } else {
+                    let (right, removed) = self.remove_rec(n.right.take(), id);
+                    n.right = right;
+                    n.update_size();
+                    (Some(n), removed)
+                }
+            }
+            None => (None, None),
+        }
+    }
+
+    fn remove_leftmost(&self, mut node: Box<Node<I, A>>) -> (Tree<I, A>, IoTDevice<I, A>) {
+        match node.left.take() {
+            Some(left) => {
+                let (left, dev) = self.remove_leftmost(left);
+                node.left = left;
+                node.update_size();
+                (Some(node), dev)
+            }
+            None => (node.right.take(), node.dev),
+        }
+    }
+
+    pub fn walk(&self, callback: impl Fn(&IoTDevice<I, A>)) {
+        self.iter().for_each(callback)
+    }
+
+    // Devices with ids inside `range`, in the same order as `iter`. Subtrees
+    // that can't overlap the range are never visited.
+    pub fn range(&self, range: impl RangeBounds<I>) -> Range<'_, I, A> {
+        Range {
+            pending: self.root.as_deref().map(Step::Sub).into_iter().collect(),
+            bounds: (range.start_bound().cloned(), range.end_bound().cloned()),
+        }
+    }
+
+    // Larger ids live on the left, so the smallest id is the rightmost node.
+    pub fn min(&self) -> Option<&IoTDevice<I, A>> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some(&node.dev)
+    }
+
+    pub fn max(&self) -> Option<&IoTDevice<I, A>> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some(&node.dev)
+    }
+
+    // The device with the largest id less than or equal to `id`.
+    pub fn floor(&self, id: I) -> Option<&IoTDevice<I, A>> {
+        self.closest(&id, true, true)
+    }
+
+    // The device with the smallest id greater than or equal to `id`.
+    pub fn ceiling(&self, id: I) -> Option<&IoTDevice<I, A>> {
+        self.closest(&id, false, true)
+    }
+
+    // The device with the smallest id strictly greater than `id`.
+    pub fn successor(&self, id: I) -> Option<&IoTDevice<I, A>> {
+        self.closest(&id, false, false)
+    }
+
+    // The device with the largest id strictly less than `id`.
+    pub fn predecessor(&self, id: I) -> Option<&IoTDevice<I, A>> {
+        self.closest(&id, true, false)
+    }
+
+    // The device with the k-th smallest id, counting from 0.
+    pub fn select(&self, k: usize) -> Option<&IoTDevice<I, A>> {
+        let mut k = k;
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            // Smaller ids live on the right
+            let smaller = Node::size(&n.right);
+            if k < smaller {
+                node = n.right.as_deref();
+            } else if k == smaller {
+                return Some(&n.dev);
+            } else {
+                k -= smaller + 1;
+                node = n.left.as_deref();
+            }
+        }
+        None
+    }
+
+    // How many devices have an id less than `id`.
+    pub fn rank(&self, id: I) -> usize {
+        let mut rank = 0;
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            if n.dev.id < id {
+                rank += Node::size(&n.right) + 1;
+                node = n.left.as_deref();
+            } else {
+                node = n.right.as_deref();
+            }
+        }
+        rank
+    }
+
+    fn closest(&self, id: &I, below: bool, inclusive: bool) -> Option<&IoTDevice<I, A>> {
+        let mut best = None;
+        let mut node = self.root.as_deref();
+        while let Some(n) = node {
+            if inclusive && n.dev.id == *id {
+                return Some(&n.dev);
+            }
+            let candidate = if below {
+                n.dev.id < *id
+            } else {
+                n.dev.id > *id
+            };
+            if candidate {
+                best = Some(&n.dev);
+            }
+            // Closer ids are larger (left) when looking below and a candidate
+            // was found, or when looking above and none was found
+            node = if candidate == below {
+                n.left.as_deref()
+            } else {
+                n.right.as_deref()
+            };
+        }
+        best
+    }
+}
+
+// Secondary indexes keep their keys in ordered maps, so only registries
+// with ordered addresses and paths can have them.
+impl<I: PartialOrd + Clone, A: Ord + Clone> DeviceRegistry<I, A> {
+    // Keeps devices findable by address and by path without a full walk.
+    pub fn enable_indexes(&mut self) {
+        let mut indexes = Indexes::new();
+        for device in self.iter() {
+            indexes.add(device);
+        }
+        self.indexes = Some(indexes);
+    }
+
+    pub fn disable_indexes(&mut self) {
+        self.indexes = None;
+    }
+
+    // Falls back to a full walk if indexes are disabled, or were emptied by
+    // `iter_mut` and not rebuilt yet.
+    pub fn find_by_address(&self, address: &A) -> Vec<&IoTDevice<I, A>> {
+        match &self.indexes {
+            Some(indexes) if !indexes.stale => {
+                let indexed = indexes.by_address.get(address).into_iter().flatten();
+                self.indexed(indexed, indexes, |d| d.address == *address)
+            }
+            _ => self.iter().filter(|d| d.address == *address).collect(),
+        }
+    }
+
+    // Falls back to a full walk if indexes are disabled, or were emptied by
+    // `iter_mut` and not rebuilt yet.
+    pub fn find_by_path_prefix(&self, prefix: &str) -> Vec<&IoTDevice<I, A>>
+    where
+        A: Borrow<str>,
+    {
+        let matches = |d: &IoTDevice<I, A>| d.path.borrow().starts_with(prefix);
+        match &self.indexes {
+            Some(indexes) if !indexes.stale => {
+                let indexed = indexes
+                    .by_path
+                    .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+                    .take_while(|(path, _)| (*path).borrow().starts_with(prefix))
+                    .flat_map(|(_, ids)| ids);
+                self.indexed(indexed, indexes, matches)
+            }
+            _ => self.iter().filter(|d| matches(d)).collect(),
+        }
+    }
+
+    // The devices behind `ids`, plus those missing from the maps because
+    // they were handed out as `&mut` that match now.
+    fn indexed<'a>(
+        &'a self,
+        ids: impl Iterator<Item = &'a I>,
+        indexes: &'a Indexes<I, A>,
+        matches: impl Fn(&IoTDevice<I, A>) -> bool,
+    ) -> Vec<&'a IoTDevice<I, A>> {
+        let pending = indexes.pending.iter().filter_map(|id| self.lookup(id));
+        ids.filter_map(|id| self.lookup(id))
+            .chain(pending.filter(|d| matches(d)))
+            .collect()
+    }
+}
+
+// Shape diagnostics. These avoid recursion as well, since they matter most
+// when the tree has degenerated.
+impl<I, A> DeviceRegistry<I, A> {
+    // Number of levels, 0 for an empty registry.
+    pub fn height(&self) -> usize {
+        self.depth_histogram().len()
+    }
+
+    // Number of devices at each depth, starting with the root at depth 0.
+    pub fn depth_histogram(&self) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        let mut level: Vec<&Node<I, A>> = self.root.as_deref().into_iter().collect();
+        while !level.is_empty() {
+            histogram.push(level.len());
+            level = level
+                .iter()
+                .flat_map(|n| n.left.as_deref().into_iter().chain(n.right.as_deref()))
+                .collect();
+        }
+        histogram
+    }
+
+    // Whether the heights of the two subtrees of every node differ by at
+    // most one.
+    pub fn is_balanced(&self) -> bool {
+        // Post-order over the subtrees, folding their heights on a stack
+        let mut pending = vec![Step::Sub(self.root.as_deref())];
+        let mut heights: Vec<usize> = Vec::new();
+        while let Some(step) = pending.pop() {
+            match step {
+                Step::Sub(None) => heights.push(0),
+                Step::Sub(Some(n)) => {
+                    pending.push(Step::Dev(n));
+                    pending.push(Step::Sub(n.right.as_deref()));
+                    pending.push(Step::Sub(n.left.as_deref()));
+                }
+                Step::Dev(_) => {
+                    let right = heights.pop().unwrap_or(0);
+                    let left = heights.pop().unwrap_or(0);
+                    if left.abs_diff(right) > 1 {
+                        return false;
+                    }
+                    heights.push(1 + left.max(right));
+                }
+            }
+        }
+        true
+    }
+
+    // Renders the tree as a Graphviz DOT digraph, labelling nodes with
+    // their device id and edges with the side they hang off.
+    pub fn to_dot(&self) -> String
+    where
+        I: Debug,
+    {
+        let mut dot = String::from("digraph DeviceRegistry {\n");
+        let mut queue: VecDeque<(&Node<I, A>, usize)> =
+            self.root.as_deref().map(|n| (n, 0)).into_iter().collect();
+        let mut next_name = 1;
+        while let Some((n, name)) = queue.pop_front() {
+            let label = format!("{:?}", n.dev.id)
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            let _ = writeln!(dot, "    n{} [label=\"{}\"];", name, label);
+            for (side, child) in [("left", &n.left), ("right", &n.right)] {
+                if let Some(child) = child.as_deref() {
+                    let _ = writeln!(dot, "    n{} -> n{} [label=\"{}\"];", name, next_name, side);
+                    queue.push_back((child, next_name));
+                    next_name += 1;
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// A slot in the registry for a single id, which may or may not hold a device.
+pub enum Entry<'a, I, A> {
+    Occupied(OccupiedEntry<'a, I, A>),
+    Vacant(VacantEntry<'a, I, A>),
+}
+
+pub struct OccupiedEntry<'a, I, A> {
+    registry: &'a mut DeviceRegistry<I, A>,
+    id: I,
+}
+
+pub struct VacantEntry<'a, I, A> {
+    registry: &'a mut DeviceRegistry<I, A>,
+    id: I,
+}
+
+impl<'a, I: PartialOrd + Clone, A: Clone> Entry<'a, I, A> {
+    pub fn key(&self) -> &I {
+        match self {
+            Entry::Occupied(e) => &e.id,
+            Entry::Vacant(e) => &e.id,
+        }
+    }
+
+    pub fn or_insert(self, address: impl Into<A>, path: impl Into<A>) -> &'a mut IoTDevice<I, A> {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(address, path),
+        }
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut IoTDevice<I, A>)) -> Self {
+        if let Entry::Occupied(e) = &mut self {
+            f(e.get_mut());
+        }
+        self
+    }
+}
+
+// Lookups are repeated on access rather than holding on to the node, which
+// keeps the entry free of borrows into the tree.
+impl<'a, I: PartialOrd + Clone, A: Clone> OccupiedEntry<'a, I, A> {
+    pub fn get(&self) -> &IoTDevice<I, A> {
+        self.registry
+            .lookup(&self.id)
+            .expect("occupied entry without a device")
+    }
+
+    pub fn get_mut(&mut self) -> &mut IoTDevice<I, A> {
+        self.registry
+            .find_mut_tracked(&self.id)
+            .expect("occupied entry without a device")
+    }
+
+    pub fn into_mut(self) -> &'a mut IoTDevice<I, A> {
+        self.registry
+            .find_mut_tracked(&self.id)
+            .expect("occupied entry without a device")
+    }
+
+    // Swaps in new address and path, returning the previous device.
+    pub fn insert(&mut self, address: impl Into<A>, path: impl Into<A>) -> IoTDevice<I, A> {
+        let device = IoTDevice::new(self.id.clone(), address, path);
+        mem::replace(self.get_mut(), device)
+    }
+
+    pub fn remove(self) -> IoTDevice<I, A> {
+        self.registry
+            .remove(self.id)
+            .expect("occupied entry without a device")
+    }
+}
+
+impl<'a, I: PartialOrd + Clone, A: Clone> VacantEntry<'a, I, A> {
+    pub fn insert(self, address: impl Into<A>, path: impl Into<A>) -> &'a mut IoTDevice<I, A> {
+        self.registry
+            .insert(IoTDevice::new(self.id.clone(), address, path));
+        self.registry
+            .find_mut_tracked(&self.id)
+            .expect("vacant entry insert did not add a device")
+    }
+}
+
+// In-order iteration (the order `walk` uses, i.e. descending ids) keeps its
+// own stack of pending work, so degenerate trees can't overflow the call
+// stack. Front and back expand subtrees lazily from the two ends of that
+// deque, so they never hand out the same device.
+impl<I, A> DeviceRegistry<I, A> {
+    pub fn iter(&self) -> Iter<'_, I, A> {
+        Iter {
+            pending: self.root.as_deref().map(Step::Sub).into_iter().collect(),
+        }
+    }
+
+    // Node before its subtrees, e.g. to rebuild the same shape elsewhere.
+    pub fn pre_order(&self) -> Traversal<'_, I, A> {
+        self.traversal(Order::Pre)
+    }
+
+    // Subtrees before their node.
+    pub fn post_order(&self) -> Traversal<'_, I, A> {
+        self.traversal(Order::Post)
+    }
+
+    // Breadth first, one level of the tree after the other.
+    pub fn level_order(&self) -> Traversal<'_, I, A> {
+        self.traversal(Order::Level)
+    }
+
+    fn traversal(&self, order: Order) -> Traversal<'_, I, A> {
+        Traversal {
+            pending: self.root.as_deref().map(Step::Sub).into_iter().collect(),
+            order,
+        }
+    }
+
+    // Changing a device's `id` through this breaks the tree's ordering.
+    // Secondary indexes are rebuilt by the next change to the registry.
+    pub fn iter_mut(&mut self) -> IterMut<'_, I, A> {
+        if let Some(indexes) = self.indexes.as_mut() {
+            indexes.clear();
+            indexes.stale = true;
+        }
+        IterMut {
+            pending: self
+                .root
+                .as_deref_mut()
+                .map(Step::Sub)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+// Work left for an iterator: a device to yield or a subtree to expand
+enum Step<D, N> {
+    Dev(D),
+    Sub(N),
+}
+type Pending<D, N> = VecDeque<Step<D, N>>;
+
+pub struct Iter<'a, I, A> {
+    pending: Pending<&'a IoTDevice<I, A>, &'a Node<I, A>>,
+}
+
+impl<'a, I, A> Iterator for Iter<'a, I, A> {
+    type Item = &'a IoTDevice<I, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    if let Some(right) = n.right.as_deref() {
+                        self.pending.push_front(Step::Sub(right));
+                    }
+                    self.pending.push_front(Step::Dev(&n.dev));
+                    if let Some(left) = n.left.as_deref() {
+                        self.pending.push_front(Step::Sub(left));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, I, A> DoubleEndedIterator for Iter<'a, I, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    if let Some(left) = n.left.as_deref() {
+                        self.pending.push_back(Step::Sub(left));
+                    }
+                    self.pending.push_back(Step::Dev(&n.dev));
+                    if let Some(right) = n.right.as_deref() {
+                        self.pending.push_back(Step::Sub(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Order {
+    Pre,
+    Post,
+    Level,
+}
+
+// Pre- and post-order treat `pending` as a stack, level order as a queue.
+pub struct Traversal<'a, I, A> {
+    pending: Pending<&'a IoTDevice<I, A>, &'a Node<I, A>>,
+    order: Order,
+}
+
+impl<'a, I, A> Iterator for Traversal<'a, I, A> {
+    type Item = &'a IoTDevice<I, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let step = match self.order {
+                Order::Level => self.pending.pop_front()?,
+                Order::Pre | Order::Post => self.pending.pop_back()?,
+            };
+            let n = match step {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => n,
+            };
+            let left = n.left.as_deref().map(Step::Sub);
+            let right = n.right.as_deref().map(Step::Sub);
+            match self.order {
+                Order::Pre => {
+                    self.pending.extend(right);
+                    self.pending.extend(left);
+                    return Some(&n.dev);
+                }
+                Order::Post => {
+                    self.pending.push_back(Step::Dev(&n.dev));
+                    self.pending.extend(right);
+                    self.pending.extend(left);
+                }
+                Order::Level => {
+                    self.pending.extend(left);
+                    self.pending.extend(right);
+                    return Some(&n.dev);
+                }
+            }
+        }
+    }
+}
+
+pub struct IterMut<'a, I, A> {
+    pending: Pending<&'a mut IoTDevice<I, A>, &'a mut Node<I, A>>,
+}
+
+impl<'a, I, A> Iterator for IterMut<'a, I, A> {
+    type Item = &'a mut IoTDevice<I, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    let Node {
+                        dev, left, right, ..
+                    } = n;
+                    if let Some(right) = right.as_deref_mut() {
+                        self.pending.push_front(Step::Sub(right));
+                    }
+                    self.pending.push_front(Step::Dev(dev));
+                    if let Some(left) = left.as_deref_mut() {
+                        self.pending.push_front(Step::Sub(left));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, I, A> DoubleEndedIterator for IterMut<'a, I, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    let Node {
+                        dev, left, right, ..
+                    } = n;
+                    if let Some(left) = left.as_deref_mut() {
+                        self.pending.push_back(Step::Sub(left));
+                    }
+                    self.pending.push_back(Step::Dev(dev));
+                    if let Some(right) = right.as_deref_mut() {
+                        self.pending.push_back(Step::Sub(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct IntoIter<I, A> {
+    pending: Pending<IoTDevice<I, A>, Box<Node<I, A>>>,
+}
+
+impl<I, A> Iterator for IntoIter<I, A> {
+    type Item = IoTDevice<I, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    let Node {
+                        dev, left, right, ..
+                    } = *n;
+                    if let Some(right) = right {
+                        self.pending.push_front(Step::Sub(right));
+                    }
+                    self.pending.push_front(Step::Dev(dev));
+                    if let Some(left) = left {
+                        self.pending.push_front(Step::Sub(left));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<I, A> DoubleEndedIterator for IntoIter<I, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => {
+                    let Node {
+                        dev, left, right, ..
+                    } = *n;
+                    if let Some(left) = left {
+                        self.pending.push_back(Step::Sub(left));
+                    }
+                    self.pending.push_back(Step::Dev(dev));
+                    if let Some(right) = right {
+                        self.pending.push_back(Step::Sub(right));
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct Range<'a, I, A> {
+    pending: Pending<&'a IoTDevice<I, A>, &'a Node<I, A>>,
+    bounds: (Bound<I>, Bound<I>),
+}
+
+impl<'a, I: PartialOrd, A> Range<'a, I, A> {
+    // Queues the in-range parts of `n` at the front or back, skipping the
+    // larger (left) side once past the end and the smaller (right) side
+    // once before the start.
+    fn expand(&mut self, n: &'a Node<I, A>, front: bool) {
+        let past_end = match &self.bounds.1 {
+            Bound::Included(e) => n.dev.id > *e,
+            Bound::Excluded(e) => n.dev.id >= *e,
+            Bound::Unbounded => false,
+        };
+        let before_start = match &self.bounds.0 {
+            Bound::Included(s) => n.dev.id < *s,
+            Bound::Excluded(s) => n.dev.id <= *s,
+            Bound::Unbounded => false,
+        };
+        let steps = [
+            n.left.as_deref().filter(|_| !past_end).map(Step::Sub),
+            Some(&n.dev)
+                .filter(|d| self.bounds.contains(&d.id))
+                .map(Step::Dev),
+            n.right.as_deref().filter(|_| !before_start).map(Step::Sub),
+        ];
+        if front {
+            for step in steps.into_iter().rev().flatten() {
+                self.pending.push_front(step);
+            }
+        } else {
+            for step in steps.into_iter().flatten() {
+                self.pending.push_back(step);
+            }
+        }
+    }
+}
+
+impl<'a, I: PartialOrd, A> Iterator for Range<'a, I, A> {
+    type Item = &'a IoTDevice<I, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_front()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => self.expand(n, true),
+            }
+        }
+    }
+}
+
+impl<'a, I: PartialOrd, A> DoubleEndedIterator for Range<'a, I, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.pending.pop_back()? {
+                Step::Dev(dev) => return Some(dev),
+                Step::Sub(n) => self.expand(n, false),
+            }
+        }
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Default for DeviceRegistry<I, A> {
+    fn default() -> Self {
+        DeviceRegistry::new_empty()
+    }
+}
+
+// Lists the devices in ascending id order.
+impl<I: Debug, A: Debug> Debug for DeviceRegistry<I, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter().rev()).finish()
+    }
+}
+
+// Builds a balanced tree in O(n log(n)) from input in any order, later
+// devices replacing earlier ones with the same id.
+impl<I: PartialOrd + Clone, A: Clone> FromIterator<IoTDevice<I, A>> for DeviceRegistry<I, A> {
+    fn from_iter<It: IntoIterator<Item = IoTDevice<I, A>>>(iter: It) -> Self {
+        DeviceRegistry::from_sorted(iter.into_iter().collect())
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Extend<IoTDevice<I, A>> for DeviceRegistry<I, A> {
+    fn extend<It: IntoIterator<Item = IoTDevice<I, A>>>(&mut self, iter: It) {
+        for device in iter {
+            self.add(device);
+        }
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Collection for DeviceRegistry<I, A> {
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    // Enabled indexes stay enabled, just empty.
+    fn clear(&mut self) {
+        // Draining drops the nodes one at a time, however deep the tree is
+        IntoIter {
+            pending: self.root.take().map(Step::Sub).into_iter().collect(),
+        }
+        .for_each(drop);
+        if let Some(indexes) = self.indexes.as_mut() {
+            indexes.clear();
+        }
+        self.length = 0;
+    }
+}
+
+// Devices are stored under their id, so `insert` overwrites the device's
+// id with the key it is given. A device with id 9 inserted under key 5 is
+// found under 5 from then on, and has id 5.
+impl<I: PartialOrd + Clone, A: Clone> OrderedMap for DeviceRegistry<I, A> {
+    type Key = I;
+    type Value = IoTDevice<I, A>;
+
+    fn insert(&mut self, key: I, mut value: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        value.id = key;
+        DeviceRegistry::insert(self, value)
+    }
+
+    fn get(&self, key: &I) -> Option<IoTDevice<I, A>> {
+        self.lookup(key).cloned()
+    }
+
+    fn keys(&self) -> Vec<I> {
+        self.iter().rev().map(|d| d.id.clone()).collect()
+    }
+}
+
+impl<I, A> IntoIterator for DeviceRegistry<I, A> {
+    type Item = IoTDevice<I, A>;
+    type IntoIter = IntoIter<I, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            pending: self.root.map(Step::Sub).into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, I, A> IntoIterator for &'a DeviceRegistry<I, A> {
+    type Item = &'a IoTDevice<I, A>;
+    type IntoIter = Iter<'a, I, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, I, A> IntoIterator for &'a mut DeviceRegistry<I, A> {
+    type Item = &'a mut IoTDevice<I, A>;
+    type IntoIter = IterMut<'a, I, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Registries are written as a plain sequence of devices in ascending id
+// order, which reads back through `from_sorted` without any rebalancing.
+// Indexes are not part of the data and stay disabled after reading.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{DeviceRegistry, IoTDevice};
+
+    impl<I: Serialize, A: Serialize> Serialize for DeviceRegistry<I, A> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter().rev())
+        }
     }
 
-    fn walk_in_order(&self, node: &Tree<I, A>, callback: &impl Fn(&IoTDevice<I, A>)) {
-        if let Some(n) = node {
-            self.walk_in_order(&n.left, callback);
-            callback(&n.dev);
-            self.walk_in_order(&n.right, callback);
+    impl<'de, I, A> Deserialize<'de> for DeviceRegistry<I, A>
+    where
+        I: PartialOrd + Clone + Deserialize<'de>,
+        A: Clone + Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let devices = Vec::<IoTDevice<I, A>>::deserialize(deserializer)?;
+            Ok(devices.into_iter().collect())
         }
     }
 }
@@ -0,0 +1,169 @@
+// Requirements
+//    1. Keep a history of commands that change some state, like the
+//    configuration of a device.
+//    2. Undo commands by walking the log backward and redo them by
+//    walking it forward again.
+//    3. Jump to any point in the history.
+//    4. Replay a whole log onto a fresh state.
+
+// Execute is O(1) plus dropping undone commands. Undo and redo walk to the
+// current position from the nearer end of the log, so they are O(1) at
+// either end and O(n) in the middle. Seeking walks the log once.
+
+// Positives
+//    1. Only the commands are stored, never copies of the state.
+//    2. The position is just a count, so the history holds no references
+//    into the log and the log stays free to move.
+// Negatives
+//    1. Every command has to know how to take itself back.
+//    2. Seeking far away runs every command in between.
+//    3. Executing after an undo throws the undone commands away.
+//    4. Undoing deep in a long history walks the log to get there.
+use super::{
+    DoublyLinkedList::{BetterTransactionLog, ValueRef},
+    Traits::Collection,
+};
+
+// A change to a state of type `S` that can be taken back.
+pub trait Command<S> {
+    fn apply(&self, state: &mut S);
+
+    // Undoes `apply`, given the state right after it.
+    fn revert(&self, state: &mut S);
+}
+
+// The command at `index`, walked to from the nearer end of the log.
+fn command_at<C>(log: &BetterTransactionLog<C>, index: usize) -> Option<ValueRef<'_, C>> {
+    let len = log.len();
+    if index < len / 2 {
+        log.iter().nth(index)
+    } else {
+        log.iter().nth_back(len.checked_sub(index + 1)?)
+    }
+}
+
+pub struct CommandHistory<C, S> {
+    log: BetterTransactionLog<C>,
+    state: S,
+    // Number of applied commands, counting from the head of the log
+    position: usize,
+}
+
+impl<C: Command<S>, S> CommandHistory<C, S> {
+    pub fn new_empty(state: S) -> CommandHistory<C, S> {
+        CommandHistory::from_log(BetterTransactionLog::new_empty(), state)
+    }
+
+    // Takes over `log` with none of its commands applied to `state` yet,
+    // `replay` applies all of them.
+    pub fn from_log(log: BetterTransactionLog<C>, state: S) -> CommandHistory<C, S> {
+        CommandHistory {
+            log,
+            state,
+            position: 0,
+        }
+    }
+
+    // Applies `command` and records it after the current position. Any
+    // undone commands after it are dropped.
+    pub fn execute(&mut self, command: C) {
+        command.apply(&mut self.state);
+        while self.log.len() > self.position {
+            self.log.pop_bwd();
+        }
+        self.log.append_bwd(command);
+        self.position += 1;
+    }
+
+    // Reverts the last applied command. Returns false if there is none.
+    pub fn undo(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        if let Some(command) = command_at(&self.log, self.position) {
+            command.borrow().revert(&mut self.state);
+        }
+        true
+    }
+
+    // Applies the first undone command. Returns false if there is none.
+    pub fn redo(&mut self) -> bool {
+        if self.position == self.log.len() {
+            return false;
+        }
+        if let Some(command) = command_at(&self.log, self.position) {
+            command.borrow().apply(&mut self.state);
+        }
+        self.position += 1;
+        true
+    }
+
+    // Undoes or redoes until `position` commands are applied, or all of
+    // them if the log is shorter. Walks the log once.
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.log.len());
+        if position < self.position {
+            let undone = self
+                .log
+                .iter()
+                .skip(position)
+                .take(self.position - position);
+            for command in undone.rev() {
+                command.borrow().revert(&mut self.state);
+            }
+        } else {
+            let redone = self
+                .log
+                .iter()
+                .skip(self.position)
+                .take(position - self.position);
+            for command in redone {
+                command.borrow().apply(&mut self.state);
+            }
+        }
+        self.position = position;
+    }
+
+    // Applies every command that is not applied yet.
+    pub fn replay(&mut self) {
+        self.seek(self.log.len());
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.log.len()
+    }
+}
+
+impl<C, S> CommandHistory<C, S> {
+    // Number of applied commands.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    // Every recorded command, the undone ones included.
+    pub fn log(&self) -> &BetterTransactionLog<C> {
+        &self.log
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    // Hands over the log and the state as of the current position.
+    pub fn into_parts(self) -> (BetterTransactionLog<C>, S) {
+        (self.log, self.state)
+    }
+}
@@ -1,11 +1,18 @@
 use std::{
-    cell::{Ref, RefCell},
+    cell::{Ref, RefCell, RefMut},
+    fmt,
+    iter::Rev,
+    marker::PhantomData,
+    mem,
     rc::Rc,
 };
 
-#[derive(Debug, Clone)]
+use super::Traits::{Collection, Queue, Sequence};
+
+// No derived Debug: it would follow `prev` and `next` around in circles
 struct Node<T> {
-    value: T,
+    // Only ever `None` once the node is out of the log
+    value: Option<T>,
     next: Link<T>,
     prev: Link<T>,
 }
@@ -18,9 +25,13 @@ type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 //      that order.
 //      3. Move forward and backward through the log.
 //      4. Moves don't consume the log.
+//      5. Insert and remove anywhere through a cursor.
 
 // Access & Search are Avg O(n)
 // Insertion and Deletion are Avg O(1)
+// Appending a whole log is O(1), and so are splitting and splicing through a
+// cursor. By index, `split_off` and `splice` first walk to the spot from the
+// closer end, O(n). Reversing touches every node, O(n).
 
 // Positives
 //    1.Low overhead allocation per item (but more than the singly
@@ -29,7 +40,7 @@ type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 //    3. Mutation while iterating is possible.
 //    4.Implementation is more complex but still fairly simple.
 //    5. Inserts, deletes, append, and prepend remain efficient.
-//    6. Efficient reversion.
+//    6. Reversing needs no new nodes, only relinking.
 
 // Negatives
 //    1. Indexing is still inefficient.
@@ -37,8 +48,10 @@ type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 //    jumping around too.
 //    3. An additional pointer has to be stored per node.
 //    4. Implementation is more complex.
+//    5. Nodes are shared between their neighbours, so walking the log
+//    can't lend out plain references: iterators and peeks hand out
+//    `ValueRef` handles that borrow the value when asked.
 
-#[derive(Debug, Clone)]
 pub struct BetterTransactionLog<T> {
     head: Link<T>,
     tail: Link<T>,
@@ -48,11 +61,23 @@ pub struct BetterTransactionLog<T> {
 impl<T> Node<T> {
     fn new(value: T) -> Rc<RefCell<Node<T>>> {
         Rc::new(RefCell::new(Node {
-            value: value,
+            value: Some(value),
             next: None,
             prev: None,
         }))
     }
+
+    fn value(&self) -> &T {
+        self.value
+            .as_ref()
+            .expect("a node in the log always holds a value")
+    }
+
+    fn value_mut(&mut self) -> &mut T {
+        self.value
+            .as_mut()
+            .expect("a node in the log always holds a value")
+    }
 }
 
 impl<T> BetterTransactionLog<T> {
@@ -88,8 +113,10 @@ impl<T> BetterTransactionLog<T> {
         self.tail = Some(new_head);
     }
 
+    // A cursor or value handle may still hold on to the popped node, so the
+    // value is taken out of it rather than the node unwrapped.
     pub fn pop_fwd(&mut self) -> Option<T> {
-        self.head.take().map(|old_head| {
+        self.head.take().and_then(|old_head| {
             if let Some(next) = old_head.borrow_mut().next.take() {
                 next.borrow_mut().prev.take();
                 self.head = Some(next);
@@ -97,16 +124,13 @@ impl<T> BetterTransactionLog<T> {
                 self.tail.take();
             }
             self.length -= 1;
-            Rc::try_unwrap(old_head)
-                .ok()
-                .expect("Panic!")
-                .into_inner()
-                .value
+            let value = old_head.borrow_mut().value.take();
+            value
         })
     }
 
     pub fn pop_bwd(&mut self) -> Option<T> {
-        self.tail.take().map(|old_tail| {
+        self.tail.take().and_then(|old_tail| {
             if let Some(prev) = old_tail.borrow_mut().prev.take() {
                 prev.borrow_mut().next.take();
                 self.tail = Some(prev);
@@ -114,82 +138,698 @@ impl<T> BetterTransactionLog<T> {
                 self.head.take();
             }
             self.length -= 1;
-            Rc::try_unwrap(old_tail)
-                .ok()
-                .expect("Panic!")
-                .into_inner()
-                .value
+            let value = old_tail.borrow_mut().value.take();
+            value
         })
     }
 
-    pub fn peek_fwd(&self) -> Option<Ref<T>> {
+    pub fn peek_fwd(&self) -> Option<Ref<'_, T>> {
         self.head
             .as_ref()
-            .map(|node| Ref::map(node.borrow(), |node| &node.value))
+            .map(|node| Ref::map(node.borrow(), Node::value))
     }
 
-    pub fn peek_bwd(&self) -> Option<Ref<T>> {
+    pub fn peek_bwd(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
-            .map(|node| Ref::map(node.borrow(), |node| &node.value))
+            .map(|node| Ref::map(node.borrow(), Node::value))
+    }
+    // Walks from head to tail, leaving the log as it is. Walk backward with
+    // `.rev()`, or take from both ends at once.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            remaining: self.length as usize,
+            log: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.head.clone(),
+            back: self.tail.clone(),
+            remaining: self.length as usize,
+            log: PhantomData,
+        }
+    }
+
+    pub fn iter_fwd(&self) -> Iter<'_, T> {
+        self.iter()
+    }
+
+    pub fn iter_bwd(&self) -> Rev<Iter<'_, T>> {
+        self.iter().rev()
+    }
+
+    // A cursor on the head, or on the ghost position if the log is empty.
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head.clone(),
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail.clone(),
+            index: self.len().saturating_sub(1),
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head.clone(),
+            index: 0,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail.clone(),
+            index: self.len().saturating_sub(1),
+            list: self,
+        }
+    }
+
+    // The neighbours of `node`, where `None` is the ghost position past the
+    // tail that wraps around to the head.
+    fn after(&self, node: &Link<T>) -> Link<T> {
+        match node {
+            Some(node) => node.borrow().next.clone(),
+            None => self.head.clone(),
+        }
+    }
+
+    fn before(&self, node: &Link<T>) -> Link<T> {
+        match node {
+            Some(node) => node.borrow().prev.clone(),
+            None => self.tail.clone(),
+        }
+    }
+
+    // Links a new node in between `prev` and `next`, which have to be
+    // neighbours. `None` stands for the respective end of the log.
+    fn link_between(&mut self, prev: Link<T>, next: Link<T>, value: T) {
+        let new = Node::new(value);
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = Some(new.clone()),
+            None => self.head = Some(new.clone()),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(new.clone()),
+            None => self.tail = Some(new.clone()),
+        }
+        let mut new = new.borrow_mut();
+        new.prev = prev;
+        new.next = next;
+        self.length += 1;
+    }
+
+    // Links every node of `other` in between `prev` and `next`, which have
+    // to be neighbours, leaving `other` empty.
+    fn splice_between(&mut self, prev: Link<T>, next: Link<T>, other: &mut Self) {
+        let (Some(head), Some(tail)) = (other.head.take(), other.tail.take()) else {
+            return;
+        };
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = Some(head.clone()),
+            None => self.head = Some(head.clone()),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(tail.clone()),
+            None => self.tail = Some(tail.clone()),
+        }
+        head.borrow_mut().prev = prev;
+        tail.borrow_mut().next = next;
+        self.length += mem::take(&mut other.length);
+    }
+
+    // Moves every command of `other` to the tail of this log in O(1),
+    // leaving `other` empty. (`append_bwd` already adds a single command.)
+    pub fn append_log(&mut self, other: &mut Self) {
+        self.splice_between(self.tail.clone(), None, other);
+    }
+
+    // A cursor on the command at `at`, or on the ghost position for the
+    // length of the log, walked to from whichever end is closer.
+    fn cursor_at(&mut self, at: usize) -> CursorMut<'_, T> {
+        let length = self.len();
+        assert!(at <= length, "cannot go to {} of {}", at, length);
+        if at < length / 2 {
+            let mut cursor = self.cursor_front_mut();
+            for _ in 0..at {
+                cursor.move_next();
+            }
+            cursor
+        } else {
+            let mut cursor = self.cursor_back_mut();
+            if at == length {
+                cursor.move_next();
+            }
+            for _ in at + 1..length {
+                cursor.move_prev();
+            }
+            cursor
+        }
+    }
+
+    // Cuts the log in two, keeping the first `at` commands and handing back
+    // the rest. Walks to the cut first, `CursorMut::split_after` cuts where
+    // a cursor already is in O(1).
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let length = self.len();
+        assert!(at <= length, "cannot split off at {} of {}", at, length);
+        match at {
+            0 => mem::take(self),
+            _ => self.cursor_at(at - 1).split_after(),
+        }
+    }
+
+    // Moves every command of `other` in before the command at `at`,
+    // leaving `other` empty. Walks there first, `CursorMut::splice_before`
+    // splices where a cursor already is in O(1).
+    pub fn splice(&mut self, at: usize, other: &mut Self) {
+        self.cursor_at(at).splice_before(other);
+    }
+
+    // Turns the log around in place by swapping every node's links, O(n).
+    pub fn reverse(&mut self) {
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            let mut node = node.borrow_mut();
+            let node = &mut *node;
+            mem::swap(&mut node.next, &mut node.prev);
+            current = node.prev.clone();
+        }
+        mem::swap(&mut self.head, &mut self.tail);
+    }
+}
+
+// Copies the values into new nodes. Sharing the nodes instead would make
+// popping from either copy panic.
+impl<T: Clone> Clone for BetterTransactionLog<T> {
+    fn clone(&self) -> Self {
+        self.iter().map(|value| value.borrow().clone()).collect()
+    }
+}
+
+impl<T> Default for BetterTransactionLog<T> {
+    fn default() -> Self {
+        BetterTransactionLog::new_empty()
+    }
+}
+
+// The nodes keep each other alive, so they have to be unlinked to be freed
+impl<T> Drop for BetterTransactionLog<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Collection for BetterTransactionLog<T> {
+    fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    fn clear(&mut self) {
+        self.tail = None;
+        // Break the cycles node by node, otherwise nothing would be freed
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            let mut node = node.borrow_mut();
+            node.prev = None;
+            current = node.next.take();
+        }
+        self.length = 0;
+    }
+}
+
+// Pushes at the tail and pops from the head, like `append_bwd` and
+// `pop_fwd`.
+impl<T: Clone> Sequence for BetterTransactionLog<T> {
+    type Item = T;
+
+    fn push(&mut self, item: T) {
+        self.append_bwd(item);
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.iter().nth(index).map(|value| value.borrow().clone())
+    }
+}
+
+impl<T: Clone> Queue for BetterTransactionLog<T> {
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_fwd()
+    }
+}
+
+// Lists the values from head to tail.
+impl<T: fmt::Debug> fmt::Debug for BetterTransactionLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for BetterTransactionLog<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut log = BetterTransactionLog::new_empty();
+        log.extend(iter);
+        log
+    }
+}
+
+// Appends at the tail, like `append_bwd`.
+impl<T> Extend<T> for BetterTransactionLog<T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        for value in iter {
+            self.append_bwd(value);
+        }
+    }
+}
+
+// A value in a borrowed log, for when there is no `&T` to hand out: a
+// reference into a node would outlive the guard of the node before it. The
+// handle holds on to the node instead and lends the value out through
+// `borrow`, and keeps the log borrowed for as long as it is used.
+pub struct ValueRef<'a, T> {
+    node: Rc<RefCell<Node<T>>>,
+    log: PhantomData<&'a BetterTransactionLog<T>>,
+}
+
+impl<T> ValueRef<'_, T> {
+    fn new(node: Rc<RefCell<Node<T>>>) -> Self {
+        ValueRef {
+            node,
+            log: PhantomData,
+        }
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.node.borrow(), Node::value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ValueRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.borrow().fmt(f)
+    }
+}
+
+// Moves through the log in both directions without changing it. Only the
+// current node is kept, its neighbours are looked up when needed. Past the
+// tail there is a ghost position (`current` is `None`) that wraps around to
+// the head.
+pub struct Cursor<'a, T> {
+    list: &'a BetterTransactionLog<T>,
+    current: Link<T>,
+    // Index of `current`, meaningless on the ghost position
+    index: usize,
+}
+
+impl<T> Cursor<'_, T> {
+    // Index of the current value, `None` on the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        self.current = self.list.after(&self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index.checked_sub(1).unwrap_or(self.list.len()),
+            None => self.list.len().saturating_sub(1),
+        };
+        self.current = self.list.before(&self.current);
+    }
+
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.current
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), Node::value))
+    }
+
+    pub fn peek_next(&self) -> Option<ValueRef<'_, T>> {
+        self.list.after(&self.current).map(ValueRef::new)
     }
-    pub fn iter_bwd(self) -> ListIterator<T> {
-        ListIterator::new(self.tail)
+
+    pub fn peek_prev(&self) -> Option<ValueRef<'_, T>> {
+        self.list.before(&self.current).map(ValueRef::new)
     }
+}
 
-    pub fn iter_fwd(self) -> ListIterator<T> {
-        ListIterator::new(self.head.clone())
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Cursor {
+            list: self.list,
+            current: self.current.clone(),
+            index: self.index,
+        }
     }
 }
 
-pub struct ListIterator<T> {
+// A cursor that can also insert and remove values where it points.
+pub struct CursorMut<'a, T> {
+    list: &'a mut BetterTransactionLog<T>,
     current: Link<T>,
+    index: usize,
 }
 
-impl<T> ListIterator<T> {
-    fn new(start_at: Link<T>) -> ListIterator<T> {
-        ListIterator { current: start_at }
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn move_next(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index + 1,
+            None => 0,
+        };
+        self.current = self.list.after(&self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.index = match self.current {
+            Some(_) => self.index.checked_sub(1).unwrap_or(self.list.len()),
+            None => self.list.len().saturating_sub(1),
+        };
+        self.current = self.list.before(&self.current);
+    }
+
+    pub fn current(&mut self) -> Option<RefMut<'_, T>> {
+        self.current
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), Node::value_mut))
+    }
+
+    pub fn peek_next(&self) -> Option<ValueRef<'_, T>> {
+        self.list.after(&self.current).map(ValueRef::new)
     }
+
+    pub fn peek_prev(&self) -> Option<ValueRef<'_, T>> {
+        self.list.before(&self.current).map(ValueRef::new)
+    }
+
+    // A read-only cursor at the same position.
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self.list,
+            current: self.current.clone(),
+            index: self.index,
+        }
+    }
+
+    // Inserts `value` after the current one, or at the head when on the
+    // ghost position.
+    pub fn insert_after(&mut self, value: T) {
+        let next = self.list.after(&self.current);
+        self.list.link_between(self.current.clone(), next, value);
+    }
+
+    // Inserts `value` before the current one, or at the tail when on the
+    // ghost position.
+    pub fn insert_before(&mut self, value: T) {
+        let prev = self.list.before(&self.current);
+        self.list.link_between(prev, self.current.clone(), value);
+        self.index += 1;
+    }
+
+    // Moves every value of `other` in after the current one, or to the
+    // head when on the ghost position.
+    pub fn splice_after(&mut self, other: &mut BetterTransactionLog<T>) {
+        let next = self.list.after(&self.current);
+        self.list.splice_between(self.current.clone(), next, other);
+    }
+
+    // Moves every value of `other` in before the current one, or to the
+    // tail when on the ghost position.
+    pub fn splice_before(&mut self, other: &mut BetterTransactionLog<T>) {
+        let prev = self.list.before(&self.current);
+        self.index += other.len();
+        self.list.splice_between(prev, self.current.clone(), other);
+    }
+
+    // Takes the current value out and moves on to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let current = self.current.take()?;
+        let mut node = current.borrow_mut();
+        let prev = node.prev.take();
+        let next = node.next.take();
+        match &prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.list.head = next.clone(),
+        }
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev,
+            None => self.list.tail = prev,
+        }
+        self.list.length -= 1;
+        self.current = next;
+        node.value.take()
+    }
+
+    // Splits off everything after the current value. On the ghost position
+    // that is the whole log.
+    pub fn split_after(&mut self) -> BetterTransactionLog<T> {
+        let Some(current) = &self.current else {
+            return mem::take(self.list);
+        };
+        let Some(head) = current.borrow_mut().next.take() else {
+            return BetterTransactionLog::new_empty();
+        };
+        head.borrow_mut().prev = None;
+        let length = self.list.length - self.index as u64 - 1;
+        self.list.length -= length;
+        BetterTransactionLog {
+            head: Some(head),
+            tail: self.list.tail.replace(current.clone()),
+            length,
+        }
+    }
+
+    // Splits off everything before the current value, which becomes the
+    // head. On the ghost position that is the whole log.
+    pub fn split_before(&mut self) -> BetterTransactionLog<T> {
+        let Some(current) = &self.current else {
+            return mem::take(self.list);
+        };
+        let Some(tail) = current.borrow_mut().prev.take() else {
+            return BetterTransactionLog::new_empty();
+        };
+        tail.borrow_mut().next = None;
+        let length = mem::take(&mut self.index) as u64;
+        self.list.length -= length;
+        BetterTransactionLog {
+            head: self.list.head.replace(current.clone()),
+            tail: Some(tail),
+            length,
+        }
+    }
+}
+
+// Takes values from the front and the back until the two ends meet. Each
+// end holds on to its next node, since a reference to it could not outlive
+// the guard of the node it was reached through, and values are handed out
+// as `ValueRef`s rather than `&T`.
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    log: PhantomData<&'a BetterTransactionLog<T>>,
 }
 
-impl<T> Iterator for ListIterator<T>
-where
-    T: Clone,
-{
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = ValueRef<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.take()?;
+        self.remaining -= 1;
+        self.front = node.borrow().next.clone();
+        Some(ValueRef::new(node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.take()?;
+        self.remaining -= 1;
+        self.back = node.borrow().prev.clone();
+        Some(ValueRef::new(node))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            front: self.front.clone(),
+            back: self.back.clone(),
+            remaining: self.remaining,
+            log: PhantomData,
+        }
+    }
+}
+
+// A value in a mutably borrowed log. Every value is handed out once, so
+// the handles never share a node.
+pub struct ValueMut<'a, T> {
+    node: Rc<RefCell<Node<T>>>,
+    log: PhantomData<&'a mut BetterTransactionLog<T>>,
+}
+
+impl<T> ValueMut<'_, T> {
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.node.borrow(), Node::value)
+    }
+
+    pub fn borrow_mut(&mut self) -> RefMut<'_, T> {
+        RefMut::map(self.node.borrow_mut(), Node::value_mut)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ValueMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.borrow().fmt(f)
+    }
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    log: PhantomData<&'a mut BetterTransactionLog<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = ValueMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.take()?;
+        self.remaining -= 1;
+        self.front = node.borrow().next.clone();
+        Some(ValueMut {
+            node,
+            log: PhantomData,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.take()?;
+        self.remaining -= 1;
+        self.back = node.borrow().prev.clone();
+        Some(ValueMut {
+            node,
+            log: PhantomData,
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Pops the values off either end.
+pub struct IntoIter<T>(BetterTransactionLog<T>);
+
+impl<T> Iterator for IntoIter<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let current = &self.current;
-        let mut result = None;
-
-        self.current = match current {
-            Some(ref current) => {
-                let current = current.borrow();
-                result = Some(current.value.clone());
-                current.next.clone()
-            }
-            None => None,
-        };
-        result
+        self.0.pop_fwd()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.0.length as usize;
+        (remaining, Some(remaining))
     }
 }
 
-impl<T> DoubleEndedIterator for ListIterator<T>
-where
-    T: Clone,
-{
+impl<T> DoubleEndedIterator for IntoIter<T> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let current = &self.current;
-        let mut result = None;
-
-        self.current = match current {
-            Some(ref current) => {
-                let current = current.borrow();
-                result = Some(current.value.clone());
-                current.prev.clone()
+        self.0.pop_bwd()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for BetterTransactionLog<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BetterTransactionLog<T> {
+    type Item = ValueRef<'a, T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut BetterTransactionLog<T> {
+    type Item = ValueMut<'a, T>;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Logs are written as a plain sequence of commands from head to tail.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::BetterTransactionLog;
+
+    impl<T: Serialize> Serialize for BetterTransactionLog<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.length as usize))?;
+            for value in self.iter() {
+                seq.serialize_element(&*value.borrow())?;
             }
-            None => None,
-        };
-        result
+            seq.end()
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for BetterTransactionLog<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+        }
     }
 }
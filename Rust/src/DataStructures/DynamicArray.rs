@@ -1,4 +1,6 @@
-use std::cmp;
+use std::{cmp, fmt};
+
+use super::Traits::{Collection, Sequence};
 
 type Node<T> = Option<T>;
 const MIN_SIZE: usize = 8;
@@ -69,6 +71,68 @@ impl<T: Copy> TimestampSaver<T> {
     }
 }
 
+impl<T: Copy> Default for TimestampSaver<T> {
+    fn default() -> Self {
+        TimestampSaver::new_empty()
+    }
+}
+
+// Clearing keeps the capacity for the next round of timestamps.
+impl<T> Collection for TimestampSaver<T> {
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn clear(&mut self) {
+        for slot in self.buf[..self.length].iter_mut() {
+            *slot = None;
+        }
+        self.length = 0;
+    }
+}
+
+impl<T: Copy> Sequence for TimestampSaver<T> {
+    type Item = T;
+
+    fn push(&mut self, item: T) {
+        self.append(item);
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.buf[..self.length].get(index).copied().flatten()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TimestampSaver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.buf[..self.length].iter().flatten())
+            .finish()
+    }
+}
+
+impl<T: Copy> FromIterator<T> for TimestampSaver<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut saver = TimestampSaver::new_empty();
+        saver.extend(iter);
+        saver
+    }
+}
+
+impl<T: Copy> Extend<T> for TimestampSaver<T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        let iter = iter.into_iter();
+        // Grow once up front instead of once per step
+        let wanted = self.length + iter.size_hint().0;
+        if wanted > self.cap {
+            self.grow(wanted);
+        }
+        for value in iter {
+            self.append(value);
+        }
+    }
+}
+
 impl<T: Copy> IntoIterator for TimestampSaver<T> {
     type Item = T;
     type IntoIter = ListIterator<T>;
@@ -121,3 +185,23 @@ impl<T: Copy> DoubleEndedIterator for ListIterator<T> {
         }
     }
 }
+
+// Only the saved timestamps are written, not the spare capacity.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::TimestampSaver;
+
+    impl<T: Copy + Serialize> Serialize for TimestampSaver<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.buf[..self.length].iter().flatten())
+        }
+    }
+
+    impl<'de, T: Copy + Deserialize<'de>> Deserialize<'de> for TimestampSaver<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+        }
+    }
+}
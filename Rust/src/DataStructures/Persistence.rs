@@ -0,0 +1,496 @@
+// Requirements
+//    1. Save a device registry to a CSV or JSON file.
+//    2. Rebuild the registry from such a file on start up.
+//    3. Point at the offending line when a file is malformed.
+
+// Both formats are written and parsed by hand to keep the crate free of
+// dependencies. Ids, addresses and paths round-trip exactly, including
+// separators, quotes, line breaks and non-ASCII text.
+//
+// CSV follows RFC 4180: a `id,address,path` header, one device per record
+// and fields quoted whenever they contain a comma, quote or line break.
+// JSON is an array with one `{"id": .., "address": .., "path": ..}` object
+// per device. Ids that look like JSON numbers are written as numbers.
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    iter::Peekable,
+    path::Path,
+    str::{Chars, FromStr},
+};
+
+use super::BinarySearchTree::{DeviceRegistry, IoTDevice};
+
+const CSV_HEADER: [&str; 3] = ["id", "address", "path"];
+
+#[derive(Debug)]
+pub enum PersistError {
+    Io(io::Error),
+    Malformed { line: usize, message: String },
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistError::Io(e) => write!(f, "{}", e),
+            PersistError::Malformed { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for PersistError {}
+
+impl From<io::Error> for PersistError {
+    fn from(e: io::Error) -> Self {
+        PersistError::Io(e)
+    }
+}
+
+fn malformed(line: usize, message: impl Into<String>) -> PersistError {
+    PersistError::Malformed {
+        line,
+        message: message.into(),
+    }
+}
+
+impl<I, A> DeviceRegistry<I, A>
+where
+    I: PartialOrd + Clone + Display + FromStr,
+    A: Clone + AsRef<str> + From<String>,
+{
+    pub fn to_csv(&self) -> String {
+        let mut csv = CSV_HEADER.join(",");
+        csv.push('\n');
+        for device in self.iter() {
+            let fields = [
+                device.id.to_string(),
+                device.address.as_ref().to_string(),
+                device.path.as_ref().to_string(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn from_csv(input: &str) -> Result<DeviceRegistry<I, A>, PersistError> {
+        let mut records = parse_csv(input)?.into_iter();
+        match records.next() {
+            Some((_, header)) if header == CSV_HEADER => {}
+            Some((line, _)) => return Err(malformed(line, "expected an id,address,path header")),
+            None => return Err(malformed(1, "missing the id,address,path header")),
+        }
+
+        let mut parsed = Vec::new();
+        for (line, fields) in records {
+            let [id, address, path]: [String; 3] =
+                fields.try_into().map_err(|f: Vec<String>| {
+                    malformed(line, format!("expected 3 fields, found {}", f.len()))
+                })?;
+            parsed.push(parse_device(line, &id, address, path)?);
+        }
+        build_registry(parsed)
+    }
+
+    pub fn to_json(&self) -> String {
+        let devices: Vec<String> = self
+            .iter()
+            .map(|device| {
+                let id = device.id.to_string();
+                let id = if is_json_number(&id) {
+                    id
+                } else {
+                    json_string(&id)
+                };
+                format!(
+                    "  {{\"id\": {}, \"address\": {}, \"path\": {}}}",
+                    id,
+                    json_string(device.address.as_ref()),
+                    json_string(device.path.as_ref())
+                )
+            })
+            .collect();
+        if devices.is_empty() {
+            "[]\n".to_string()
+        } else {
+            format!("[\n{}\n]\n", devices.join(",\n"))
+        }
+    }
+
+    pub fn from_json(input: &str) -> Result<DeviceRegistry<I, A>, PersistError> {
+        let mut reader = JsonReader {
+            chars: input.chars().peekable(),
+            line: 1,
+        };
+        let mut parsed = Vec::new();
+
+        reader.expect('[')?;
+        if reader.peek() == Some(']') {
+            reader.expect(']')?;
+        } else {
+            loop {
+                let line = reader.line_after_whitespace();
+                let [id, address, path] = reader.device()?;
+                parsed.push(parse_device(line, &id, address, path)?);
+                match reader.next_token()? {
+                    ',' => continue,
+                    ']' => break,
+                    c => {
+                        return Err(malformed(
+                            reader.line,
+                            format!("expected , or ], found {:?}", c),
+                        ))
+                    }
+                }
+            }
+        }
+        if let Some(c) = reader.peek() {
+            return Err(malformed(
+                reader.line,
+                format!("unexpected {:?} after the device list", c),
+            ));
+        }
+        build_registry(parsed)
+    }
+
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        Ok(fs::write(path, self.to_csv())?)
+    }
+
+    pub fn load_csv(path: impl AsRef<Path>) -> Result<DeviceRegistry<I, A>, PersistError> {
+        DeviceRegistry::from_csv(&fs::read_to_string(path)?)
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        Ok(fs::write(path, self.to_json())?)
+    }
+
+    pub fn load_json(path: impl AsRef<Path>) -> Result<DeviceRegistry<I, A>, PersistError> {
+        DeviceRegistry::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+fn parse_device<I: FromStr, A: From<String>>(
+    line: usize,
+    id: &str,
+    address: String,
+    path: String,
+) -> Result<(usize, IoTDevice<I, A>), PersistError> {
+    let id: I = id
+        .parse()
+        .map_err(|_| malformed(line, format!("invalid id {:?}", id)))?;
+    Ok((line, IoTDevice::new(id, address, path)))
+}
+
+// Builds a balanced registry from devices parsed in file order, so files
+// saved in any id order load in O(n log(n)). A repeated id is reported on
+// the first line that repeats one.
+fn build_registry<I, A>(
+    mut parsed: Vec<(usize, IoTDevice<I, A>)>,
+) -> Result<DeviceRegistry<I, A>, PersistError>
+where
+    I: PartialOrd + Clone + Display,
+    A: Clone,
+{
+    // A stable sort keeps devices with the same id in line order
+    parsed.sort_by(|a, b| a.1.id.partial_cmp(&b.1.id).unwrap_or(Ordering::Equal));
+    let duplicate = parsed
+        .windows(2)
+        .filter(|w| w[0].1.id == w[1].1.id)
+        .map(|w| &w[1])
+        .min_by_key(|(line, _)| *line);
+    if let Some((line, device)) = duplicate {
+        return Err(malformed(*line, format!("duplicate id {}", device.id)));
+    }
+    Ok(DeviceRegistry::from_sorted(
+        parsed.into_iter().map(|(_, device)| device).collect(),
+    ))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Splits CSV text into records, each with the line it starts on. Blank
+// lines are skipped.
+fn parse_csv(input: &str) -> Result<Vec<(usize, Vec<String>)>, PersistError> {
+    let mut records = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = Vec::new();
+        loop {
+            let mut field = String::new();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => return Err(malformed(start, "unterminated quoted field")),
+                    }
+                }
+                if !matches!(chars.peek(), None | Some(',' | '\n' | '\r')) {
+                    return Err(malformed(line, "unexpected text after a quoted field"));
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    match c {
+                        ',' | '\n' | '\r' => break,
+                        '"' => return Err(malformed(line, "quote inside an unquoted field")),
+                        _ => field.push(c),
+                    }
+                    chars.next();
+                }
+            }
+            fields.push(field);
+
+            match chars.next() {
+                Some(',') => continue,
+                Some('\r') => {
+                    if chars.next() != Some('\n') {
+                        return Err(malformed(line, "carriage return without line feed"));
+                    }
+                    line += 1;
+                }
+                Some(_) => line += 1,
+                None => {}
+            }
+            break;
+        }
+        if fields != [""] {
+            records.push((start, fields));
+        }
+    }
+    Ok(records)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Whether `s` is a number literal as JSON defines it.
+fn is_json_number(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let digits = |s: &str| s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    let int = digits(s);
+    if int == 0 || (int > 1 && s.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &s[int..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let n = digits(fraction);
+        if n == 0 {
+            return false;
+        }
+        rest = &fraction[n..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let n = digits(exponent);
+        if n == 0 {
+            return false;
+        }
+        rest = &exponent[n..];
+    }
+    rest.is_empty()
+}
+
+// Just enough of a JSON parser for a flat list of devices.
+struct JsonReader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '\n' => self.line += 1,
+                ' ' | '\t' | '\r' => {}
+                _ => break,
+            }
+            self.chars.next();
+        }
+    }
+
+    fn line_after_whitespace(&mut self) -> usize {
+        self.skip_whitespace();
+        self.line
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn next_token(&mut self) -> Result<char, PersistError> {
+        self.skip_whitespace();
+        self.chars
+            .next()
+            .ok_or_else(|| malformed(self.line, "unexpected end of input"))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PersistError> {
+        match self.next_token()? {
+            c if c == expected => Ok(()),
+            c => Err(malformed(
+                self.line,
+                format!("expected {:?}, found {:?}", expected, c),
+            )),
+        }
+    }
+
+    // One device object, as its id, address and path.
+    fn device(&mut self) -> Result<[String; 3], PersistError> {
+        let start = self.line;
+        let mut fields: [Option<String>; 3] = [None, None, None];
+        self.expect('{')?;
+        if self.peek() != Some('}') {
+            loop {
+                let key = self.string()?;
+                self.expect(':')?;
+                let slot = CSV_HEADER
+                    .iter()
+                    .position(|k| *k == key)
+                    .ok_or_else(|| malformed(self.line, format!("unknown field {:?}", key)))?;
+                // Only the id may be a number
+                let value = match (slot, self.peek()) {
+                    (_, Some('"')) => self.string()?,
+                    (0, _) => self.number()?,
+                    _ => return Err(malformed(self.line, format!("{} must be a string", key))),
+                };
+                if fields[slot].replace(value).is_some() {
+                    return Err(malformed(self.line, format!("duplicate field {:?}", key)));
+                }
+                match self.next_token()? {
+                    ',' => continue,
+                    '}' => break,
+                    c => {
+                        return Err(malformed(
+                            self.line,
+                            format!("expected , or }}, found {:?}", c),
+                        ))
+                    }
+                }
+            }
+        } else {
+            self.expect('}')?;
+        }
+
+        let [id, address, path] = fields;
+        match (id, address, path) {
+            (Some(id), Some(address), Some(path)) => Ok([id, address, path]),
+            _ => Err(malformed(start, "a device needs an id, address and path")),
+        }
+    }
+
+    fn number(&mut self) -> Result<String, PersistError> {
+        self.skip_whitespace();
+        let mut number = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                break;
+            }
+            number.push(c);
+            self.chars.next();
+        }
+        if is_json_number(&number) {
+            Ok(number)
+        } else {
+            Err(malformed(self.line, format!("invalid number {:?}", number)))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, PersistError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let c = match self.chars.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(malformed(self.line, "invalid escape sequence")),
+                    };
+                    s.push(c);
+                }
+                Some('\n') | None => return Err(malformed(self.line, "unterminated string")),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    // The code point of a \u escape, whose backslash and `u` are consumed.
+    fn unicode_escape(&mut self) -> Result<char, PersistError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            // A surrogate pair, spelled as two escapes
+            if self.chars.next() != Some('\\') || self.chars.next() != Some('u') {
+                return Err(malformed(self.line, "unpaired surrogate"));
+            }
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(malformed(self.line, "unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| malformed(self.line, "invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, PersistError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .chars
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| malformed(self.line, "invalid unicode escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+}
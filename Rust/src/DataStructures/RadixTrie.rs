@@ -0,0 +1,305 @@
+// Requirements
+//    1. Store IoT device objects by their IP address.
+//    2. List every device inside a subnet (e.g. 10.1.0.0/16).
+//    3. Find the devices sharing the longest prefix with an address.
+//    4. Remove devices again.
+
+// Access, Search, Insertion and Deletion are O(k), k being the address
+// width (32 bits for IPv4, 128 bits for IPv6)
+
+// Positives
+//    1. Lookup cost depends on the address width, not the device count.
+//    2. Devices of a subnet share a subtree, so enumerating them is cheap.
+//    3. Path compression (Patricia) keeps one node per branching point.
+
+// Negatives
+//    1. Only works for keys that are bit strings.
+//    2. Splitting and merging nodes makes updates more involved.
+//    3. Every node is a separate heap allocation.
+use std::{fmt, mem, net::IpAddr};
+
+use super::{
+    BinarySearchTree::{parse_address, DeviceError, DeviceRegistry, IoTDevice, TypedIoTDevice},
+    Traits::Collection,
+};
+
+// Addresses are stored left aligned in a u128, IPv4 in the top 32 bits.
+type Tree<I, A> = Option<Box<Node<I, A>>>;
+struct Node<I, A> {
+    key: u128,
+    // Number of leading bits of `key` shared by everything below this node
+    len: u8,
+    // Only set on full width nodes, which are the leaves
+    devices: Vec<IoTDevice<I, A>>,
+    children: [Tree<I, A>; 2],
+}
+
+impl<I, A> Node<I, A> {
+    fn new(key: u128, len: u8) -> Box<Node<I, A>> {
+        Box::new(Node {
+            key: mask(key, len),
+            len,
+            devices: Vec::new(),
+            children: [None, None],
+        })
+    }
+
+    // All devices below this node.
+    fn collect<'a>(&'a self, result: &mut Vec<&'a IoTDevice<I, A>>) {
+        let mut stack = vec![self];
+        while let Some(n) = stack.pop() {
+            result.extend(n.devices.iter());
+            stack.extend(n.children.iter().rev().flatten().map(|c| &**c));
+        }
+    }
+}
+
+fn mask(key: u128, len: u8) -> u128 {
+    match len {
+        0 => 0,
+        _ => key & (!0u128 << (128 - len as u32)),
+    }
+}
+
+fn bit(key: u128, index: u8) -> usize {
+    ((key >> (127 - index as u32)) & 1) as usize
+}
+
+fn common_len(a: u128, b: u128, max: u8) -> u8 {
+    ((a ^ b).leading_zeros() as u8).min(max)
+}
+
+fn split_address(address: IpAddr) -> (u128, u8) {
+    match address {
+        IpAddr::V4(v4) => ((u32::from(v4) as u128) << 96, 32),
+        IpAddr::V6(v6) => (u128::from(v6), 128),
+    }
+}
+
+pub struct DeviceTrie<I, A> {
+    v4: Tree<I, A>,
+    v6: Tree<I, A>,
+    pub length: usize,
+}
+
+impl<I: PartialEq + Clone, A: AsRef<str> + Clone> DeviceTrie<I, A> {
+    pub fn new_empty() -> DeviceTrie<I, A> {
+        DeviceTrie {
+            v4: None,
+            v6: None,
+            length: 0,
+        }
+    }
+
+    // Every device of the registry, rejecting the first invalid address.
+    pub fn from_registry(registry: &DeviceRegistry<I, A>) -> Result<DeviceTrie<I, A>, DeviceError> {
+        let mut trie = DeviceTrie::new_empty();
+        for device in registry.iter() {
+            trie.insert(device.clone())?;
+        }
+        Ok(trie)
+    }
+
+    fn root(&self, address: IpAddr) -> &Tree<I, A> {
+        match address {
+            IpAddr::V4(_) => &self.v4,
+            IpAddr::V6(_) => &self.v6,
+        }
+    }
+
+    fn root_mut(&mut self, address: IpAddr) -> &mut Tree<I, A> {
+        match address {
+            IpAddr::V4(_) => &mut self.v4,
+            IpAddr::V6(_) => &mut self.v6,
+        }
+    }
+
+    // Adds a device under its address, replacing (and returning) a device
+    // with the same id at that address.
+    pub fn insert(
+        &mut self,
+        device: IoTDevice<I, A>,
+    ) -> Result<Option<IoTDevice<I, A>>, DeviceError> {
+        let address = parse_address(device.address.as_ref())?;
+        Ok(self.insert_at(address, device))
+    }
+
+    // `insert` for an address that has been parsed already.
+    fn insert_at(&mut self, address: IpAddr, device: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        let (key, width) = split_address(address);
+        let previous = Self::insert_rec(self.root_mut(address), key, width, device);
+        if previous.is_none() {
+            self.length += 1;
+        }
+        previous
+    }
+
+    fn insert_rec(
+        tree: &mut Tree<I, A>,
+        key: u128,
+        width: u8,
+        device: IoTDevice<I, A>,
+    ) -> Option<IoTDevice<I, A>> {
+        let node = match tree {
+            Some(node) => node,
+            None => {
+                let mut leaf = Node::new(key, width);
+                leaf.devices.push(device);
+                *tree = Some(leaf);
+                return None;
+            }
+        };
+
+        let common = common_len(node.key, key, node.len);
+        if common < node.len {
+            // The key leaves this node's prefix early, so branch off there
+            let old = tree.take().expect("node disappeared while splitting");
+            let mut branch = Node::new(key, common);
+            let mut leaf = Node::new(key, width);
+            leaf.devices.push(device);
+            let side = bit(key, common);
+            branch.children[side] = Some(leaf);
+            branch.children[1 - side] = Some(old);
+            *tree = Some(branch);
+            None
+        } else if node.len == width {
+            match node.devices.iter_mut().find(|d| d.id == device.id) {
+                Some(existing) => Some(mem::replace(existing, device)),
+                None => {
+                    node.devices.push(device);
+                    None
+                }
+            }
+        } else {
+            Self::insert_rec(&mut node.children[bit(key, node.len)], key, width, device)
+        }
+    }
+
+    pub fn remove(&mut self, address: IpAddr, id: I) -> Option<IoTDevice<I, A>> {
+        let (key, width) = split_address(address);
+        let removed = Self::remove_rec(self.root_mut(address), key, width, &id);
+        if removed.is_some() {
+            self.length -= 1;
+        }
+        removed
+    }
+
+    fn remove_rec(tree: &mut Tree<I, A>, key: u128, width: u8, id: &I) -> Option<IoTDevice<I, A>> {
+        let node = tree.as_mut()?;
+        if common_len(node.key, key, node.len) < node.len {
+            return None;
+        }
+        let removed = if node.len == width {
+            let pos = node.devices.iter().position(|d| d.id == *id)?;
+            Some(node.devices.remove(pos))
+        } else {
+            Self::remove_rec(&mut node.children[bit(key, node.len)], key, width, id)
+        };
+
+        // Drop empty leaves and fold branches that are left with one child
+        if node.len == width {
+            if node.devices.is_empty() {
+                *tree = None;
+            }
+        } else {
+            match (node.children[0].take(), node.children[1].take()) {
+                (Some(only), None) | (None, Some(only)) => *tree = Some(only),
+                (None, None) => *tree = None,
+                (zero, one) => node.children = [zero, one],
+            }
+        }
+        removed
+    }
+
+    // Devices registered at exactly this address.
+    pub fn find(&self, address: IpAddr) -> Vec<&IoTDevice<I, A>> {
+        let (_, width) = split_address(address);
+        self.subnet(address, width)
+    }
+
+    // Every device inside `network`/`prefix_len`, e.g. 10.1.0.0/16.
+    pub fn subnet(&self, network: IpAddr, prefix_len: u8) -> Vec<&IoTDevice<I, A>> {
+        let (key, width) = split_address(network);
+        let prefix_len = prefix_len.min(width);
+        let mut result = Vec::new();
+        let mut tree = self.root(network);
+        while let Some(node) = tree {
+            let wanted = node.len.min(prefix_len);
+            if common_len(node.key, key, wanted) < wanted {
+                break;
+            }
+            if node.len >= prefix_len {
+                node.collect(&mut result);
+                break;
+            }
+            tree = &node.children[bit(key, node.len)];
+        }
+        result
+    }
+
+    // The devices sharing the longest prefix with `address`, along with the
+    // length of that prefix. An exact match has the full address width.
+    pub fn longest_prefix_match(&self, address: IpAddr) -> Option<(u8, Vec<&IoTDevice<I, A>>)> {
+        let (key, _) = split_address(address);
+        let mut node = self.root(address).as_deref()?;
+        loop {
+            let common = common_len(node.key, key, node.len);
+            if common < node.len || node.children.iter().all(Option::is_none) {
+                let mut result = Vec::new();
+                node.collect(&mut result);
+                return Some((common, result));
+            }
+            // Branches always have both children
+            node = node.children[bit(key, node.len)]
+                .as_deref()
+                .expect("branch with a missing child");
+        }
+    }
+}
+
+impl<I: PartialEq + Clone, A: AsRef<str> + Clone> Default for DeviceTrie<I, A> {
+    fn default() -> Self {
+        DeviceTrie::new_empty()
+    }
+}
+
+impl<I, A> Collection for DeviceTrie<I, A> {
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn clear(&mut self) {
+        self.v4 = None;
+        self.v6 = None;
+        self.length = 0;
+    }
+}
+
+// Lists the devices in address order, IPv4 before IPv6.
+impl<I: fmt::Debug, A: fmt::Debug> fmt::Debug for DeviceTrie<I, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut devices = Vec::new();
+        for root in [&self.v4, &self.v6].into_iter().flatten() {
+            root.collect(&mut devices);
+        }
+        f.debug_list().entries(devices).finish()
+    }
+}
+
+// Only typed devices can be collected, since their addresses are known to
+// be valid. Plain devices go through the fallible `insert`.
+impl<I: PartialEq + Clone> FromIterator<TypedIoTDevice<I>> for DeviceTrie<I, String> {
+    fn from_iter<It: IntoIterator<Item = TypedIoTDevice<I>>>(iter: It) -> Self {
+        let mut trie = DeviceTrie::new_empty();
+        trie.extend(iter);
+        trie
+    }
+}
+
+impl<I: PartialEq + Clone> Extend<TypedIoTDevice<I>> for DeviceTrie<I, String> {
+    fn extend<It: IntoIterator<Item = TypedIoTDevice<I>>>(&mut self, iter: It) {
+        for device in iter {
+            self.insert_at(device.address, device.into());
+        }
+    }
+}
@@ -0,0 +1,287 @@
+// Requirements
+//    1. Store IoT device objects (IP address, numerical name and Type).
+//    2. Retrieve IoT objects by numerical name.
+//    3. Iterate over IoT objects.
+//    4. Stay fast when device IDs arrive (mostly) sorted.
+
+// Access, Search and Insertion are Avg O(log(n))
+// Access, Search and Insertion are Worst O(log(n))
+
+// Positives
+//    1. Height is at most 2 * log(n + 1), regardless of insert order.
+//    2. Sorted input does not degenerate the tree into a list.
+//    3. Recursion depth is bounded by the (logarithmic) height.
+
+// Negatives
+//    1. More complex than a plain binary search tree.
+//    2. An additional color has to be stored per node.
+//    3. Rotations make insertion slower than in an unbalanced tree.
+//    4. Devices cannot be removed again.
+
+// Balancing follows the left-leaning variant: red links always lean
+// left, which keeps the number of cases to fix after an insert small.
+use std::{fmt, mem};
+
+use super::{BinarySearchTree::IoTDevice, Traits::Collection};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Color {
+    Red,
+    Black,
+}
+
+type Tree<I, A> = Option<Box<Node<I, A>>>;
+struct Node<I, A> {
+    pub dev: IoTDevice<I, A>,
+    color: Color,
+    left: Tree<I, A>,
+    right: Tree<I, A>,
+}
+
+impl<I, A> Node<I, A> {
+    fn new(dev: IoTDevice<I, A>) -> Box<Node<I, A>> {
+        Box::new(Node {
+            dev,
+            color: Color::Red,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn is_red(node: &Tree<I, A>) -> bool {
+        matches!(node, Some(n) if n.color == Color::Red)
+    }
+
+    fn rotate_left(mut node: Box<Node<I, A>>) -> Box<Node<I, A>> {
+        let mut right = node.right.take().expect("rotate_left without right child");
+        node.right = right.left.take();
+        right.color = node.color;
+        node.color = Color::Red;
+        right.left = Some(node);
+        right
+    }
+
+    fn rotate_right(mut node: Box<Node<I, A>>) -> Box<Node<I, A>> {
+        let mut left = node.left.take().expect("rotate_right without left child");
+        node.left = left.right.take();
+        left.color = node.color;
+        node.color = Color::Red;
+        left.right = Some(node);
+        left
+    }
+
+    fn flip_colors(node: &mut Node<I, A>) {
+        node.color = Color::Red;
+        if let Some(l) = node.left.as_mut() {
+            l.color = Color::Black;
+        }
+        if let Some(r) = node.right.as_mut() {
+            r.color = Color::Black;
+        }
+    }
+
+    fn balance(mut node: Box<Node<I, A>>) -> Box<Node<I, A>> {
+        if Node::is_red(&node.right) && !Node::is_red(&node.left) {
+            node = Node::rotate_left(node);
+        }
+        if Node::is_red(&node.left) && node.left.as_ref().is_some_and(|l| Node::is_red(&l.left)) {
+            node = Node::rotate_right(node);
+        }
+        if Node::is_red(&node.left) && Node::is_red(&node.right) {
+            Node::flip_colors(&mut node);
+        }
+        node
+    }
+}
+
+// Broken invariants reported by `BetterDeviceRegistry::validate`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RbViolation {
+    RedRoot,
+    RedRed,
+    BlackHeight,
+    Order,
+}
+
+pub struct BetterDeviceRegistry<I, A> {
+    root: Tree<I, A>,
+    pub length: usize,
+}
+
+impl<I: PartialOrd + Clone, A: Clone> BetterDeviceRegistry<I, A> {
+    pub fn new_empty() -> BetterDeviceRegistry<I, A> {
+        BetterDeviceRegistry {
+            root: None,
+            length: 0,
+        }
+    }
+
+    // Registers a device, replacing and returning any device with the same
+    // id, like `DeviceRegistry` does.
+    pub fn add(&mut self, device: IoTDevice<I, A>) -> Option<IoTDevice<I, A>> {
+        let mut replaced = None;
+        let root = self.root.take();
+        let mut root = self.add_rec(root, device, &mut replaced);
+        root.color = Color::Black;
+        self.root = Some(root);
+        if replaced.is_none() {
+            self.length += 1;
+        }
+        replaced
+    }
+
+    fn add_rec(
+        &self,
+        node: Tree<I, A>,
+        device: IoTDevice<I, A>,
+        replaced: &mut Option<IoTDevice<I, A>>,
+    ) -> Box<Node<I, A>> {
+        match node {
+            Some(mut n) => {
+                if device.id == n.dev.id {
+                    *replaced = Some(mem::replace(&mut n.dev, device));
+                } else if device.id < n.dev.id {
+                    n.left = Some(self.add_rec(n.left.take(), device, replaced));
+                } else {
+                    n.right = Some(self.add_rec(n.right.take(), device, replaced));
+                }
+                Node::balance(n)
+            }
+            None => Node::new(device),
+        }
+    }
+
+    pub fn find(&self, id: I) -> Option<IoTDevice<I, A>> {
+        self.find_r(&self.root, id)
+    }
+
+    fn find_r(&self, node: &Tree<I, A>, id: I) -> Option<IoTDevice<I, A>> {
+        match node {
+            Some(n) => {
+                if n.dev.id == id {
+                    Some(n.dev.clone())
+                } else if id < n.dev.id {
+                    self.find_r(&n.left, id)
+                } else {
+                    self.find_r(&n.right, id)
+                }
+            }
+            None => None,
+        }
+    }
+
+    // Visits devices in ascending id order.
+    pub fn walk(&self, callback: impl Fn(&IoTDevice<I, A>)) {
+        self.walk_in_order(&self.root, &callback)
+    }
+
+    fn walk_in_order(&self, node: &Tree<I, A>, callback: &impl Fn(&IoTDevice<I, A>)) {
+        if let Some(n) = node {
+            self.walk_in_order(&n.left, callback);
+            callback(&n.dev);
+            self.walk_in_order(&n.right, callback);
+        }
+    }
+
+    // Number of nodes on the longest path from the root, 0 when empty.
+    pub fn height(&self) -> usize {
+        Self::height_rec(&self.root)
+    }
+
+    fn height_rec(node: &Tree<I, A>) -> usize {
+        match node {
+            Some(n) => 1 + Self::height_rec(&n.left).max(Self::height_rec(&n.right)),
+            None => 0,
+        }
+    }
+
+    // Checks ordering, that the root is black, that no red node has a red
+    // child and that every path holds the same number of black nodes.
+    // Returns that black height (counting the empty leaves) on success.
+    pub fn validate(&self) -> Result<usize, RbViolation> {
+        if Node::is_red(&self.root) {
+            return Err(RbViolation::RedRoot);
+        }
+        self.validate_rec(&self.root, None, None)
+    }
+
+    fn validate_rec(
+        &self,
+        node: &Tree<I, A>,
+        min: Option<&I>,
+        max: Option<&I>,
+    ) -> Result<usize, RbViolation> {
+        match node {
+            Some(n) => {
+                if min.is_some_and(|m| n.dev.id < *m) || max.is_some_and(|m| n.dev.id > *m) {
+                    return Err(RbViolation::Order);
+                }
+                if n.color == Color::Red && (Node::is_red(&n.left) || Node::is_red(&n.right)) {
+                    return Err(RbViolation::RedRed);
+                }
+                let left = self.validate_rec(&n.left, min, Some(&n.dev.id))?;
+                let right = self.validate_rec(&n.right, Some(&n.dev.id), max)?;
+                if left != right {
+                    return Err(RbViolation::BlackHeight);
+                }
+                Ok(left + if n.color == Color::Black { 1 } else { 0 })
+            }
+            None => Ok(1),
+        }
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Default for BetterDeviceRegistry<I, A> {
+    fn default() -> Self {
+        BetterDeviceRegistry::new_empty()
+    }
+}
+
+impl<I, A> Collection for BetterDeviceRegistry<I, A> {
+    fn len(&self) -> usize {
+        self.length
+    }
+
+    fn clear(&mut self) {
+        self.root = None;
+        self.length = 0;
+    }
+}
+
+// Lists the devices in ascending id order.
+impl<I: fmt::Debug, A: fmt::Debug> fmt::Debug for BetterDeviceRegistry<I, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut stack = Vec::new();
+        let mut current = &self.root;
+        loop {
+            while let Some(n) = current {
+                stack.push(n);
+                current = &n.left;
+            }
+            match stack.pop() {
+                Some(n) => {
+                    list.entry(&n.dev);
+                    current = &n.right;
+                }
+                None => return list.finish(),
+            }
+        }
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> FromIterator<IoTDevice<I, A>> for BetterDeviceRegistry<I, A> {
+    fn from_iter<It: IntoIterator<Item = IoTDevice<I, A>>>(iter: It) -> Self {
+        let mut registry = BetterDeviceRegistry::new_empty();
+        registry.extend(iter);
+        registry
+    }
+}
+
+impl<I: PartialOrd + Clone, A: Clone> Extend<IoTDevice<I, A>> for BetterDeviceRegistry<I, A> {
+    fn extend<It: IntoIterator<Item = IoTDevice<I, A>>>(&mut self, iter: It) {
+        for device in iter {
+            self.add(device);
+        }
+    }
+}
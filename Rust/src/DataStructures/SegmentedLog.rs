@@ -0,0 +1,229 @@
+// Requirements
+//    1. Keep appending commands without the log growing forever.
+//    2. Roll over to a new segment after a number of entries or bytes.
+//    3. Replace everything up to a checkpoint with a snapshot.
+//    4. Drop old segments by count or by age.
+
+// Append is O(1), dropping a segment is O(1) (plus freeing its entries)
+
+// Positives
+//    1. Old entries go away a whole segment at a time, without touching
+//    the rest of the log.
+//    2. Every entry keeps its sequence number, no matter what was dropped
+//    before it.
+//    3. Snapshots bound how much has to be replayed.
+// Negatives
+//    1. Entries are encoded once just to measure them, and every size is
+//    kept until its entry goes.
+//    2. Retention only ever drops whole segments, so some old entries
+//    stay around until their segment goes.
+//    3. Replay needs the snapshot and the log to agree on the checkpoint.
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use super::{SinglyLinkedList::TransactionLog, Traits::Collection, WriteAheadLog::Record};
+
+// When the active segment is closed and a new one started. `None` means no
+// limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RollPolicy {
+    pub max_entries: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+// Which closed segments are kept. The active segment is never dropped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Retention {
+    Forever,
+    // At most this many segments, counting the active one
+    Segments(usize),
+    // Segments closed at most this long ago
+    MaxAge(Duration),
+}
+
+struct Segment<T> {
+    // Sequence number of the first entry
+    base: u64,
+    entries: TransactionLog<T>,
+    // Encoded size of every entry, in the same order
+    sizes: VecDeque<usize>,
+    bytes: usize,
+    closed: Option<Instant>,
+}
+
+impl<T> Segment<T> {
+    fn new(base: u64) -> Segment<T> {
+        Segment {
+            base,
+            entries: TransactionLog::new_empty(),
+            sizes: VecDeque::new(),
+            bytes: 0,
+            closed: None,
+        }
+    }
+}
+
+pub struct SegmentedLog<T, S = ()> {
+    // Oldest first, the last one is the active segment
+    segments: VecDeque<Segment<T>>,
+    roll: RollPolicy,
+    retention: Retention,
+    // The state as of `checkpoint`, covering every entry before it
+    snapshot: Option<(u64, S)>,
+    next_seq: u64,
+    pub length: u64,
+}
+
+impl<T: Record, S> SegmentedLog<T, S> {
+    pub fn new_empty(roll: RollPolicy, retention: Retention) -> SegmentedLog<T, S> {
+        SegmentedLog {
+            segments: VecDeque::from([Segment::new(0)]),
+            roll,
+            retention,
+            snapshot: None,
+            next_seq: 0,
+            length: 0,
+        }
+    }
+
+    fn active(&mut self) -> &mut Segment<T> {
+        self.segments
+            .back_mut()
+            .expect("there is always an active segment")
+    }
+
+    // Adds `value` and returns its sequence number.
+    pub fn append(&mut self, value: T) -> u64 {
+        let size = value.encode().len();
+        let roll = self.roll;
+        let active = self.active();
+        let too_many = roll
+            .max_entries
+            .is_some_and(|max| active.entries.len() >= max);
+        let too_big = roll.max_bytes.is_some_and(|max| active.bytes + size > max);
+        // A single entry over the byte limit still gets a segment of its own
+        if !active.entries.is_empty() && (too_many || too_big) {
+            self.roll_over();
+        }
+
+        let active = self.active();
+        active.entries.append(value);
+        active.sizes.push_back(size);
+        active.bytes += size;
+        self.length += 1;
+        self.next_seq += 1;
+        self.next_seq - 1
+    }
+
+    // Closes the active segment and starts a new one, then applies the
+    // retention policy.
+    pub fn roll_over(&mut self) {
+        let now = Instant::now();
+        self.active().closed = Some(now);
+        self.segments.push_back(Segment::new(self.next_seq));
+        self.enforce_retention_at(now);
+    }
+
+    // Drops the closed segments the retention policy no longer covers and
+    // returns how many entries went with them. Age based retention only
+    // catches up when this is called, so call it now and then.
+    pub fn enforce_retention(&mut self) -> u64 {
+        self.enforce_retention_at(Instant::now())
+    }
+
+    // Like `enforce_retention`, with segment ages measured up to `now`.
+    pub fn enforce_retention_at(&mut self, now: Instant) -> u64 {
+        let mut dropped = 0;
+        while self.segments.len() > 1 {
+            let oldest = &self.segments[0];
+            let expired = match self.retention {
+                Retention::Forever => false,
+                Retention::Segments(max) => self.segments.len() > max,
+                Retention::MaxAge(age) => oldest
+                    .closed
+                    .is_some_and(|c| now.saturating_duration_since(c) > age),
+            };
+            if !expired {
+                break;
+            }
+            dropped += self.drop_oldest();
+        }
+        dropped
+    }
+
+    fn drop_oldest(&mut self) -> u64 {
+        let mut segment = self.segments.pop_front().expect("no segment to drop");
+        let count = segment.entries.len() as u64;
+        // Clearing unlinks the nodes one by one instead of recursively
+        segment.entries.clear();
+        self.length -= count;
+        count
+    }
+
+    // Drops every entry with a sequence number below `seq`.
+    pub fn truncate_before(&mut self, seq: u64) {
+        while self.segments.len() > 1 && self.segments[1].base <= seq {
+            self.drop_oldest();
+        }
+        let oldest = &mut self.segments[0];
+        while oldest.base < seq {
+            match oldest.entries.pop().and(oldest.sizes.pop_front()) {
+                Some(size) => {
+                    oldest.bytes -= size;
+                    oldest.base += 1;
+                    self.length -= 1;
+                }
+                None => {
+                    oldest.base = seq.min(self.next_seq);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Stores `state` as the result of every entry so far and drops those
+    // entries. Replay then starts from the snapshot.
+    pub fn checkpoint(&mut self, state: S) {
+        let seq = self.next_seq;
+        self.truncate_before(seq);
+        self.snapshot = Some((seq, state));
+    }
+
+    // The latest snapshot and the sequence number of the first entry it
+    // does not cover.
+    pub fn snapshot(&self) -> Option<(u64, &S)> {
+        self.snapshot.as_ref().map(|(seq, state)| (*seq, state))
+    }
+
+    // Sequence number of the oldest entry still kept.
+    pub fn first_seq(&self) -> u64 {
+        self.segments[0].base
+    }
+
+    // Sequence number the next append will get.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    // Walks the kept entries from oldest to newest with their sequence
+    // numbers.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &T)> {
+        self.segments
+            .iter()
+            .flat_map(|segment| (segment.base..).zip(segment.entries.iter()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
@@ -1,10 +1,11 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{fmt, mem, ptr::NonNull};
 
-type Link<T> = Rc<RefCell<Node<T>>>;
-#[derive(Clone)]
+use super::Traits::{Collection, Queue, Sequence};
+
+type Link<T> = Option<Box<Node<T>>>;
 struct Node<T> {
     value: T,
-    next: Option<Link<T>>,
+    next: Link<T>,
 }
 // Requirements
 //      1. Append a command to an existing list.
@@ -12,7 +13,9 @@ struct Node<T> {
 //      that order.
 
 // Access & Search are Avg O(n) 
-// Insertion and Deletion are Avg O(1)
+// Insertion at the tail and Deletion from the front are O(1)
+// Appending a log is O(1), splitting and splicing walk to the spot, O(n),
+// and reversing is O(n)
 
 // Positives 
 //      1. Low overhead allocation per item.
@@ -28,19 +31,24 @@ struct Node<T> {
 //      Iteration in general involves a lot of jumping around on the
 //      heap, which takes more time and makes the operation hard to cache.
 //      2. Reversing a list is very inefficient.
-#[derive(Clone)]
+//      3. Every node has exactly one owner, the node before it, so the
+//      tail is reached through a raw pointer that every relinking method
+//      has to keep up to date.
 pub struct TransactionLog<T> {
-    head: Option<Link<T>>,
-    tail: Option<Link<T>>,
+    head: Link<T>,
+    // The last node of the chain owned by `head`, `None` when empty
+    tail: Option<NonNull<Node<T>>>,
     pub length: u64,
 }
 
+// The tail pointer only ever points into nodes the log owns, so the log is
+// as thread safe as a `Vec<T>`.
+unsafe impl<T: Send> Send for TransactionLog<T> {}
+unsafe impl<T: Sync> Sync for TransactionLog<T> {}
+
 impl<T> Node<T> {
-    fn new(value: T) -> Link<T> {
-        Rc::new(RefCell::new(Node {
-            value: value,
-            next: None,
-        }))
+    fn new(value: T) -> Box<Node<T>> {
+        Box::new(Node { value, next: None })
     }
 }
 
@@ -52,29 +60,297 @@ impl<T> TransactionLog<T> {
             length: 0,
         }
     }
-    pub fn append(&mut self, value: T) {
-        let new = Node::new(value);
-        match self.tail.take() {
-            Some(old) => old.borrow_mut().next = Some(new.clone()),
-            None => self.head = Some(new.clone()),
+
+    // The empty link behind the last node.
+    fn tail_link(&mut self) -> &mut Link<T> {
+        match self.tail {
+            // SAFETY: `tail` points at the last node owned through `head`,
+            // and `&mut self` rules out any other reference into the chain
+            Some(mut tail) => unsafe { &mut tail.as_mut().next },
+            None => &mut self.head,
         }
+    }
+
+    pub fn append(&mut self, value: T) {
+        let tail = NonNull::from(&mut **self.tail_link().insert(Node::new(value)));
+        self.tail = Some(tail);
         self.length += 1;
-        self.tail = Some(new);
     }
     // Pops from front
     pub fn pop(&mut self) -> Option<T> {
         self.head.take().map(|head| {
-            if let Some(next) = head.borrow_mut().next.take() {
-                self.head = Some(next);
-            } else {
-                self.tail.take();
+            self.head = head.next;
+            if self.head.is_none() {
+                self.tail = None;
             }
             self.length -= 1;
-            Rc::try_unwrap(head)
-                .ok()
-                .expect("Panic!")
-                .into_inner()
-                .value
+            head.value
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // Walks from head to tail, leaving the log as it is.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+            remaining: self.len(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            remaining: self.len(),
+            next: self.head.as_deref_mut(),
+        }
+    }
+
+    // Moves every command of `other` to the end of this log, leaving
+    // `other` empty. (`append` already adds a single command.) `other` is
+    // linked in behind the tail in one piece, O(1).
+    pub fn append_log(&mut self, other: &mut TransactionLog<T>) {
+        if other.head.is_some() {
+            *self.tail_link() = other.head.take();
+            self.tail = other.tail.take();
+        }
+        self.length += mem::take(&mut other.length);
+    }
+
+    // Cuts the log in two, keeping the first `at` commands and handing back
+    // the rest. Has to walk to the cut.
+    pub fn split_off(&mut self, at: usize) -> TransactionLog<T> {
+        let length = self.len();
+        assert!(at <= length, "cannot split off at {} of {}", at, length);
+        let mut link = &mut self.head;
+        let mut last = None;
+        for _ in 0..at {
+            match link {
+                Some(node) => {
+                    last = Some(NonNull::from(&mut **node));
+                    link = &mut node.next;
+                }
+                None => break,
+            }
+        }
+        let head = link.take();
+        let tail = if head.is_some() { self.tail } else { None };
+        self.tail = last;
+        self.length = at as u64;
+        TransactionLog {
+            head,
+            tail,
+            length: (length - at) as u64,
+        }
+    }
+
+    // Moves every command of `other` in before the command at `at`,
+    // leaving `other` empty. Walks to `at`, the rest is relinked in O(1).
+    pub fn splice(&mut self, at: usize, other: &mut TransactionLog<T>) {
+        let mut rest = self.split_off(at);
+        self.append_log(other);
+        self.append_log(&mut rest);
+    }
+
+    // Turns the log around in place, relinking the nodes, O(n).
+    pub fn reverse(&mut self) {
+        self.tail = self.head.as_deref_mut().map(NonNull::from);
+        let mut rest = self.head.take();
+        while let Some(mut node) = rest {
+            rest = mem::replace(&mut node.next, self.head.take());
+            self.head = Some(node);
+        }
+    }
+}
+
+// Copies the values into new nodes. Every node has a single owner, so a
+// copy can't share any of them, and a derived `Clone` would recurse once
+// per node.
+impl<T: Clone> Clone for TransactionLog<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Default for TransactionLog<T> {
+    fn default() -> Self {
+        TransactionLog::new_empty()
+    }
+}
+
+// Unlink node by node, dropping the chain in one go could recurse deeply
+impl<T> Drop for TransactionLog<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Collection for TransactionLog<T> {
+    fn len(&self) -> usize {
+        TransactionLog::len(self)
+    }
+
+    fn clear(&mut self) {
+        self.tail = None;
+        let mut current = self.head.take();
+        while let Some(mut node) = current {
+            current = node.next.take();
+        }
+        self.length = 0;
+    }
+}
+
+impl<T: Clone> Sequence for TransactionLog<T> {
+    type Item = T;
+
+    fn push(&mut self, item: T) {
+        self.append(item);
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.iter().nth(index).cloned()
+    }
+}
+
+impl<T: Clone> Queue for TransactionLog<T> {
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TransactionLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<T> for TransactionLog<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut log = TransactionLog::new_empty();
+        log.extend(iter);
+        log
+    }
+}
+
+impl<T> Extend<T> for TransactionLog<T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        for value in iter {
+            self.append(value);
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            self.remaining -= 1;
+            &node.value
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            self.remaining -= 1;
+            &mut node.value
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Pops the values off the front.
+pub struct IntoIter<T>(TransactionLog<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for TransactionLog<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a TransactionLog<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut TransactionLog<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// Logs are written as a plain sequence of commands, oldest first.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::TransactionLog;
+
+    impl<T: Serialize> Serialize for TransactionLog<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for TransactionLog<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Vec::<T>::deserialize(deserializer)?.into_iter().collect())
+        }
+    }
 }
@@ -1,10 +1,15 @@
 use std::{
     cell::RefCell,
     fmt::{self, Display},
+    mem,
     rc::Rc,
 };
 
+use super::Traits::{Collection, OrderedMap};
+
 type Link<K, V> = Option<Rc<RefCell<Node<K, V>>>>;
+// Levels used by `default()`, enough for roughly 2^16 entries
+const DEFAULT_MAX_LEVEL: usize = 16;
 #[derive(Clone)]
 struct Node<K, V> {
     next: Vec<Link<K, V>>,
@@ -44,11 +49,9 @@ impl<K, V> Node<K, V> {
     }
 }
 
-impl<K, V> BestTransactionLog<K, V>
-where
-    K: Display + PartialOrd + Copy,
-    V: Clone,
-{
+// Building up a log needs nothing from the keys and values, only
+// searching it does.
+impl<K, V> BestTransactionLog<K, V> {
     pub fn new_empty(max_level: usize) -> Self {
         BestTransactionLog {
             head: None,
@@ -79,6 +82,76 @@ where
         self.length += 1;
     }
 
+    fn get_level(&self) -> usize {
+        let mut n = 0;
+        while rand::random::<bool>() && n < self.max_level {
+            n += 1;
+        }
+        n
+    }
+}
+
+impl<K, V> BestTransactionLog<K, V>
+where
+    K: Display + PartialOrd + Copy,
+    V: Clone,
+{
+    // Puts an entry into its sorted place, for transactions that arrive
+    // late. The value of an existing key is replaced and handed back.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let head = match self.head {
+            Some(ref head) => head.clone(),
+            None => {
+                self.append(key, value);
+                return None;
+            }
+        };
+        // The head spans every level, so a new smallest entry takes over the
+        // head and the old head entry is inserted behind it instead
+        let (key, value, swapped) = {
+            let mut h = head.borrow_mut();
+            if key < h.key {
+                let old_key = mem::replace(&mut h.key, key);
+                let old_value = mem::replace(&mut h.value, value);
+                (old_key, old_value, true)
+            } else {
+                (key, value, false)
+            }
+        };
+
+        // The last node at or before `key` on every level, top level first
+        let mut update = Vec::with_capacity(self.max_level + 1);
+        let mut n = head;
+        for level in (0..=self.max_level).rev() {
+            loop {
+                let next = match n.borrow().next[level] {
+                    Some(ref next) if next.borrow().key <= key => next.clone(),
+                    _ => break,
+                };
+                n = next;
+            }
+            update.push(n.clone());
+        }
+        update.reverse();
+
+        if !swapped && update[0].borrow().key == key {
+            return Some(mem::replace(&mut update[0].borrow_mut().value, value));
+        }
+        let level = 1 + self.get_level();
+        let new = Node::new(vec![None; level], key, value);
+        for (i, prev) in update.iter().enumerate().take(level) {
+            let mut prev = prev.borrow_mut();
+            let next = prev.next[i].take();
+            if next.is_none() {
+                self.tails[i] = Some(new.clone());
+            }
+            new.borrow_mut().next[i] = next;
+            prev.next[i] = Some(new.clone());
+        }
+        self.length += 1;
+        None
+    }
+
     pub fn find(&self, key: K) -> Option<V> {
         match self.head {
             Some(ref head) => {
@@ -86,10 +159,7 @@ where
                 let node = head.clone();
                 let mut result = None;
 
-                loop {
-                    if node.borrow().next[start_level].is_none() {
-                        break;
-                    }
+                while start_level > 0 && node.borrow().next[start_level].is_none() {
                     start_level -= 1;
                 }
                 let mut n = node;
@@ -113,18 +183,83 @@ where
         }
     }
 
-    fn get_level(&self) -> usize {
-        let mut n = 0;
-        while rand::random::<bool>() && n < self.max_level {
-            n += 1;
+    fn iter_level(&self, level: usize) -> ListIterator<K, V> {
+        ListIterator::new(self.head.clone(), level)
+    }
+}
+impl<K, V> Default for BestTransactionLog<K, V>
+where
+    K: Display + PartialOrd + Copy,
+    V: Clone,
+{
+    fn default() -> Self {
+        BestTransactionLog::new_empty(DEFAULT_MAX_LEVEL)
+    }
+}
+
+impl<K, V> Collection for BestTransactionLog<K, V> {
+    fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    fn clear(&mut self) {
+        self.tails.iter_mut().for_each(|tail| *tail = None);
+        // Unlink node by node, dropping the chain in one go could recurse deeply
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            let next = mem::take(&mut node.borrow_mut().next);
+            current = next.into_iter().next().flatten();
         }
-        n
+        self.length = 0;
     }
+}
 
-    fn iter_level(&self, level: usize) -> ListIterator<K, V> {
-        ListIterator::new(self.head.clone(), level)
+impl<K, V> OrderedMap for BestTransactionLog<K, V>
+where
+    K: Display + PartialOrd + Copy,
+    V: Clone,
+{
+    type Key = K;
+    type Value = V;
+
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        BestTransactionLog::insert(self, key, value)
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.find(*key)
+    }
+
+    fn keys(&self) -> Vec<K> {
+        self.iter_level(0).map(|(key, _)| key).collect()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for BestTransactionLog<K, V>
+where
+    K: Display + PartialOrd + Copy,
+    V: Clone,
+{
+    fn from_iter<It: IntoIterator<Item = (K, V)>>(iter: It) -> Self {
+        let mut log = BestTransactionLog::default();
+        log.extend(iter);
+        log
+    }
+}
+
+// Like `append`, the keys have to arrive in ascending order.
+impl<K, V> Extend<(K, V)> for BestTransactionLog<K, V>
+where
+    K: Display + PartialOrd + Copy,
+    V: Clone,
+{
+    fn extend<It: IntoIterator<Item = (K, V)>>(&mut self, iter: It) {
+        for (key, value) in iter {
+            self.append(key, value);
+        }
     }
 }
+
 impl<K, V> IntoIterator for BestTransactionLog<K, V>
 where
     K: Display + PartialOrd + Copy,
@@ -197,3 +332,75 @@ where
         }
     }
 }
+
+// Skip lists are written as a key/value map in ascending key order. The
+// express lanes are not part of the data: reading rebuilds them with
+// log2(n) levels, and rejects keys that are out of order.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use std::{fmt, marker::PhantomData};
+
+    use serde::{
+        de::{self, MapAccess, Visitor},
+        ser::SerializeMap,
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    use super::BestTransactionLog;
+
+    impl<K: Serialize, V: Serialize> Serialize for BestTransactionLog<K, V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.length as usize))?;
+            let mut current = self.head.clone();
+            while let Some(node) = current {
+                let node = node.borrow();
+                map.serialize_entry(&node.key, &node.value)?;
+                current = node.next[0].clone();
+            }
+            map.end()
+        }
+    }
+
+    struct LogVisitor<K, V>(PhantomData<(K, V)>);
+
+    impl<'de, K, V> Visitor<'de> for LogVisitor<K, V>
+    where
+        K: PartialOrd + Copy + Deserialize<'de>,
+        V: Clone + Deserialize<'de>,
+    {
+        type Value = BestTransactionLog<K, V>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a map with keys in ascending order")
+        }
+
+        fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+            let mut entries: Vec<(K, V)> = Vec::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((key, value)) = map.next_entry()? {
+                if entries.last().is_some_and(|(last, _)| *last > key) {
+                    return Err(de::Error::custom(format!(
+                        "entry {} is out of order",
+                        entries.len()
+                    )));
+                }
+                entries.push((key, value));
+            }
+            let max_level = (usize::BITS - entries.len().leading_zeros()) as usize;
+            let mut log = BestTransactionLog::new_empty(max_level.max(1));
+            for (key, value) in entries {
+                log.append(key, value);
+            }
+            Ok(log)
+        }
+    }
+
+    impl<'de, K, V> Deserialize<'de> for BestTransactionLog<K, V>
+    where
+        K: PartialOrd + Copy + Deserialize<'de>,
+        V: Clone + Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_map(LogVisitor(PhantomData))
+        }
+    }
+}
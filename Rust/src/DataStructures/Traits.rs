@@ -0,0 +1,52 @@
+// Requirements
+//    1. Share one vocabulary between the structures, so code and
+//    benchmarks can be generic over the backing structure.
+//    2. Report sizes as usize everywhere, whatever a structure stores.
+
+// Each structure keeps its own verbs (`append`, `add`, `at`, ...) and
+// implements these traits on top of them.
+
+// Anything that holds a number of items.
+pub trait Collection {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Drops every item.
+    fn clear(&mut self);
+}
+
+// Items kept in the order they were added.
+pub trait Sequence: Collection {
+    type Item;
+
+    // Adds `item` at the end.
+    fn push(&mut self, item: Self::Item);
+
+    // A copy of the item at `index`, counting from the front.
+    fn get(&self, index: usize) -> Option<Self::Item>;
+}
+
+// A sequence that hands its items back in the order they were pushed.
+pub trait Queue: Sequence {
+    fn pop_front(&mut self) -> Option<Self::Item>;
+}
+
+// Values stored under unique keys and kept in ascending key order.
+pub trait OrderedMap: Collection {
+    type Key;
+    type Value;
+
+    // Stores `value` under `key`, handing back the value it replaced.
+    // Values that carry their own key, like devices and their id, get it
+    // overwritten with `key`.
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
+
+    // A copy of the value stored under `key`.
+    fn get(&self, key: &Self::Key) -> Option<Self::Value>;
+
+    // All keys, in ascending order.
+    fn keys(&self) -> Vec<Self::Key>;
+}
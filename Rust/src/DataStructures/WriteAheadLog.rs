@@ -0,0 +1,300 @@
+// Requirements
+//    1. Keep the transaction log across crashes and restarts.
+//    2. Write every command to disk before it shows up in the log.
+//    3. Let the caller trade durability for speed when syncing.
+//    4. Recover from a crash in the middle of a write.
+
+// Append is O(1) plus the write, opening is O(n) in the file size
+
+// Positives
+//    1. The file is only ever appended to, which is cheap for disks.
+//    2. Torn records from a crash are detected and cut off on open.
+//    3. Every record is checksummed, so bit rot does not go unnoticed.
+// Negatives
+//    1. The whole file is replayed on open.
+//    2. Commands have to be encoded to and decoded from bytes.
+//    3. The file grows forever, nothing is ever removed from it.
+//    4. Telling a torn last record from damage in the middle means
+//    searching the file behind it for a valid record, byte by byte. If
+//    the damage hits a length field, the record can claim the rest of the
+//    file and the damage passes for a torn write.
+
+// Every record is framed as
+//
+//    [length: u32 LE][crc32 of length and payload: u32 LE][payload]
+//
+// Replay stops at the first record that is incomplete or fails its
+// checksum. A crash can only ever tear the last write, so if no valid record
+// follows the length that record declares, the file is truncated there. If
+// one does, the file is damaged in the middle, and opening fails without
+// touching it.
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use super::SinglyLinkedList::{Iter, TransactionLog};
+
+const HEADER_LEN: usize = 8;
+
+// Commands that can be stored in a write-ahead log.
+pub trait Record: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl Record for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl Record for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+macro_rules! int_record {
+    ($($int:ty),*) => {
+        $(
+            impl Record for $int {
+                fn encode(&self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+
+                fn decode(bytes: &[u8]) -> Option<Self> {
+                    Some(<$int>::from_le_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+int_record!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+// When appended records are forced to disk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyncPolicy {
+    // After every append, nothing acknowledged is ever lost
+    Always,
+    // After every n appends, up to n - 1 records can be lost
+    Every(usize),
+    // Only on `sync()`, leaving the rest to the operating system
+    Never,
+}
+
+#[derive(Debug)]
+pub enum WalError {
+    Io(io::Error),
+    // A record could not be decoded, or failed its checksum with valid
+    // records still behind it
+    Corrupt { offset: u64 },
+}
+
+impl Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalError::Io(e) => write!(f, "{}", e),
+            WalError::Corrupt { offset } => {
+                write!(f, "record at byte {} is corrupt", offset)
+            }
+        }
+    }
+}
+
+impl Error for WalError {}
+
+impl From<io::Error> for WalError {
+    fn from(e: io::Error) -> Self {
+        WalError::Io(e)
+    }
+}
+
+// IEEE CRC-32, the one used by zip and Ethernet, over `parts` one after
+// the other.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in parts.iter().copied().flatten() {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub(crate) fn frame(payload: &[u8]) -> Vec<u8> {
+    let length = u32::try_from(payload.len()).expect("records are limited to 4 GiB");
+    let mut record = Vec::with_capacity(HEADER_LEN + payload.len());
+    record.extend_from_slice(&length.to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    record.extend_from_slice(payload);
+    let crc = crc32(&[&record[..4], payload]);
+    record[4..HEADER_LEN].copy_from_slice(&crc.to_le_bytes());
+    record
+}
+
+// Record payloads along with the offsets they start at
+type Records<'a> = Vec<(u64, &'a [u8])>;
+
+// The payload of the record at `offset` and where it ends, if a complete
+// record that passes its checksum starts there.
+fn frame_at(bytes: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let header = bytes.get(offset..offset + HEADER_LEN)?;
+    let length = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+    let start = offset + HEADER_LEN;
+    let payload = bytes.get(start..start.checked_add(length)?)?;
+    if crc32(&[&header[..4], payload]) != crc {
+        return None;
+    }
+    Some((payload, start + length))
+}
+
+// Splits `bytes` into record payloads with their offsets, along with the
+// length of the valid prefix. Anything after that prefix is a torn last
+// record, unless a valid record can still be found behind the span its
+// header declares: then the damage is in the middle of the log and cutting
+// it off would lose those records too. Valid-looking records inside that
+// span are just payload bytes of the torn record.
+pub(crate) fn unframe(bytes: &[u8]) -> Result<(Records<'_>, usize), WalError> {
+    let mut records = Vec::new();
+    let mut offset = 0;
+    while let Some((payload, end)) = frame_at(bytes, offset) {
+        records.push((offset as u64, payload));
+        offset = end;
+    }
+    // A header that is cut short leaves no room for anything behind it
+    let declared_end = bytes
+        .get(offset..offset + HEADER_LEN)
+        .map_or(bytes.len(), |header| {
+            let length = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+            (offset + HEADER_LEN).saturating_add(length)
+        });
+    if (declared_end..bytes.len()).any(|at| frame_at(bytes, at).is_some()) {
+        return Err(WalError::Corrupt {
+            offset: offset as u64,
+        });
+    }
+    Ok((records, offset))
+}
+
+pub struct DurableTransactionLog<T> {
+    log: TransactionLog<T>,
+    file: File,
+    policy: SyncPolicy,
+    // Size of the file up to the last complete record
+    file_len: u64,
+    unsynced: usize,
+    torn_bytes: u64,
+}
+
+impl<T: Record> DurableTransactionLog<T> {
+    // Opens or creates the log at `path` and replays it, cutting off a torn
+    // record at the end.
+    pub fn open(
+        path: impl AsRef<Path>,
+        policy: SyncPolicy,
+    ) -> Result<DurableTransactionLog<T>, WalError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        let (records, valid) = unframe(&bytes)?;
+        let log = records
+            .into_iter()
+            .map(|(offset, payload)| T::decode(payload).ok_or(WalError::Corrupt { offset }))
+            .collect::<Result<TransactionLog<T>, _>>()?;
+        let torn_bytes = (bytes.len() - valid) as u64;
+        if torn_bytes > 0 {
+            file.set_len(valid as u64)?;
+            file.sync_all()?;
+        }
+        Ok(DurableTransactionLog {
+            log,
+            file,
+            policy,
+            file_len: valid as u64,
+            unsynced: 0,
+            torn_bytes,
+        })
+    }
+
+    // Writes `value` to the file and then adds it to the log. If the write
+    // fails, the log and the file are left as they were.
+    pub fn append(&mut self, value: T) -> Result<(), WalError> {
+        let record = frame(&value.encode());
+        if let Err(e) = self.file.write_all(&record) {
+            // Do not leave half a record for later appends to land behind
+            let _ = self.file.set_len(self.file_len);
+            return Err(e.into());
+        }
+        self.file_len += record.len() as u64;
+        self.log.append(value);
+        self.unsynced += 1;
+        let due = match self.policy {
+            SyncPolicy::Always => true,
+            SyncPolicy::Every(n) => self.unsynced >= n,
+            SyncPolicy::Never => false,
+        };
+        if due {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    // Forces everything appended so far to disk.
+    pub fn sync(&mut self) -> Result<(), WalError> {
+        self.file.sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+}
+
+impl<T> DurableTransactionLog<T> {
+    pub fn log(&self) -> &TransactionLog<T> {
+        &self.log
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.log.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    // Bytes of a torn record that were cut off when opening.
+    pub fn torn_bytes(&self) -> u64 {
+        self.torn_bytes
+    }
+
+    // Closes the file, handing over the log.
+    pub fn into_log(self) -> TransactionLog<T> {
+        self.log
+    }
+}
@@ -1,8 +1,25 @@
+pub mod ArenaLinkedList;
 pub mod BinarySearchTree;
+pub mod CommandHistory;
 pub mod DoublyLinkedList;
 pub mod DynamicArray;
+pub mod Persistence;
+pub mod RadixTrie;
 pub mod RedBlackTree;
+pub mod SegmentedLog;
 pub mod SinglyLinkedList;
 pub mod SkipList;
+pub mod Traits;
+pub mod WriteAheadLog;
 
 pub mod BTree;
+
+// snake_case paths for the modules above
+pub use self::{
+    ArenaLinkedList as arena_linked_list, BTree as b_tree, BinarySearchTree as binary_search_tree,
+    CommandHistory as command_history, DoublyLinkedList as doubly_linked_list,
+    DynamicArray as dynamic_array, Persistence as persistence, RadixTrie as radix_trie,
+    RedBlackTree as red_black_tree, SegmentedLog as segmented_log,
+    SinglyLinkedList as singly_linked_list, SkipList as skip_list, Traits as traits,
+    WriteAheadLog as write_ahead_log,
+};
@@ -1,20 +1,65 @@
 #![allow(non_snake_case, dead_code)]
 
-mod Algorithms;
-mod DataStructures;
+pub mod Algorithms;
+pub mod DataStructures;
+
+// snake_case paths for the modules above
+pub use self::{Algorithms as algorithms, DataStructures as data_structures};
+
+// The structures, their device types and the traits they share, for
+// `use Rust::prelude::*;`
+pub mod prelude {
+    pub use crate::DataStructures::{
+        ArenaLinkedList::ArenaTransactionLog,
+        BTree::DeviceDatabase,
+        BinarySearchTree::{DeviceError, DeviceKind, DeviceRegistry, IoTDevice, TypedIoTDevice},
+        CommandHistory::{Command, CommandHistory},
+        DoublyLinkedList::BetterTransactionLog,
+        DynamicArray::TimestampSaver,
+        Persistence::PersistError,
+        RadixTrie::DeviceTrie,
+        RedBlackTree::BetterDeviceRegistry,
+        SegmentedLog::{Retention, RollPolicy, SegmentedLog},
+        SinglyLinkedList::TransactionLog,
+        SkipList::BestTransactionLog,
+        Traits::{Collection, OrderedMap, Queue, Sequence},
+        WriteAheadLog::{DurableTransactionLog, Record, SyncPolicy, WalError},
+    };
+}
 
 #[cfg(test)]
 mod test {
-    use std::{cell::RefCell, fmt::Debug};
+    use std::{
+        cell::RefCell,
+        cmp::Reverse,
+        fmt::Debug,
+        fs::{self, OpenOptions},
+        io::Write,
+        net::IpAddr,
+        ops::Bound,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
 
     use rand::seq::SliceRandom;
 
     use crate::DataStructures::{
-        BinarySearchTree::{DeviceRegistry, IoTDevice},
+        ArenaLinkedList::ArenaTransactionLog,
+        BTree::DeviceDatabase,
+        BinarySearchTree::{
+            DeviceError, DeviceKind, DeviceRegistry, Entry, IoTDevice, TypedIoTDevice,
+        },
+        CommandHistory::{Command, CommandHistory},
         DoublyLinkedList::BetterTransactionLog,
         DynamicArray::TimestampSaver,
+        Persistence::PersistError,
+        RadixTrie::DeviceTrie,
+        RedBlackTree::BetterDeviceRegistry,
+        SegmentedLog::{Retention, RollPolicy, SegmentedLog},
         SinglyLinkedList::TransactionLog,
         SkipList::BestTransactionLog,
+        Traits::{Collection, OrderedMap, Queue, Sequence},
+        WriteAheadLog::{frame, DurableTransactionLog, SyncPolicy, WalError},
     };
 
     fn new_device_with_id<I: Debug + Copy, A: From<String>>(id: I) -> IoTDevice<I, A> {
@@ -45,6 +90,270 @@ mod test {
         assert_eq!(list.pop(), Some("Five"));
 
         assert_eq!(list.pop(), None);
+        // Emptying the log forgets the tail
+        list.append("Six");
+        assert_eq!(list.pop(), Some("Six"));
+
+        // Appending goes straight to the tail, however long the log
+        let mut long: TransactionLog<u32> = (0..100_000).collect();
+        long.append(100_000);
+        assert_eq!(long.len(), 100_001);
+        assert_eq!(long.iter().last(), Some(&100_000));
+    }
+
+    #[test]
+    fn sll_iter() {
+        let mut list = TransactionLog::<String>::new_empty();
+        assert!(list.is_empty());
+        assert_eq!(list.iter().count(), 0);
+        for command in ["One", "Two", "Three"] {
+            list.append(command.to_string());
+        }
+        assert_eq!(list.len(), 3);
+
+        // Replaying twice leaves the log untouched
+        for _ in 0..2 {
+            let replayed: Vec<String> = list.iter().cloned().collect();
+            assert_eq!(replayed, ["One", "Two", "Three"]);
+            assert_eq!(list.len(), 3);
+        }
+        assert_eq!(list.iter().len(), 3);
+
+        for command in list.iter_mut() {
+            command.push('!');
+        }
+        for command in &mut list {
+            command.make_ascii_uppercase();
+        }
+        let audit = list.clone();
+        assert_eq!(list.pop(), Some("ONE!".to_string()));
+        list.append("Four".to_string());
+        let replayed: Vec<String> = (&list).into_iter().cloned().collect();
+        assert_eq!(replayed, ["TWO!", "THREE!", "Four"]);
+
+        // The clone does not share nodes with the original
+        assert_eq!(
+            audit.into_iter().collect::<Vec<_>>(),
+            ["ONE!", "TWO!", "THREE!"]
+        );
+        let mut owned = list.into_iter();
+        assert_eq!(owned.len(), 3);
+        assert_eq!(owned.next(), Some("TWO!".to_string()));
+        assert_eq!(owned.collect::<Vec<_>>(), ["THREE!", "Four"]);
+    }
+
+    #[test]
+    fn sll_append_split_splice_reverse() {
+        let values = |log: &TransactionLog<u8>| log.iter().copied().collect::<Vec<_>>();
+        let mut log: TransactionLog<u8> = (1..=3).collect();
+        let mut other: TransactionLog<u8> = (4..=6).collect();
+        log.append_log(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(values(&log), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(log.len(), 6);
+        log.append(7);
+        other.append_log(&mut log);
+        assert_eq!((log.len(), other.len()), (0, 7));
+        log.append_log(&mut other);
+
+        // Cut at a checkpoint, both halves keep working
+        let mut rest = log.split_off(4);
+        assert_eq!(values(&log), [1, 2, 3, 4]);
+        assert_eq!(values(&rest), [5, 6, 7]);
+        assert_eq!((log.len(), rest.len()), (4, 3));
+        log.append(8);
+        rest.append(9);
+        assert_eq!(values(&rest), [5, 6, 7, 9]);
+        assert!(log.split_off(5).is_empty());
+        let all = log.split_off(0);
+        assert!(log.is_empty());
+        log = all;
+
+        let mut merged: TransactionLog<u8> = [20, 21].into_iter().collect();
+        log.splice(1, &mut merged);
+        log.splice(0, &mut rest);
+        assert_eq!(values(&log), [5, 6, 7, 9, 1, 20, 21, 2, 3, 4, 8]);
+        assert_eq!(log.len(), 11);
+        // Splicing at the end moves the tail along
+        let mut end: TransactionLog<u8> = [10].into_iter().collect();
+        log.splice(11, &mut end);
+        log.append(11);
+        assert_eq!(log.split_off(11).into_iter().collect::<Vec<_>>(), [10, 11]);
+
+        log.reverse();
+        assert_eq!(values(&log), [8, 4, 3, 2, 21, 20, 1, 9, 7, 6, 5]);
+        log.append(0);
+        assert_eq!(log.pop(), Some(8));
+        assert_eq!(log.len(), 11);
+        assert_eq!(log.into_iter().last(), Some(0));
+    }
+
+    // Write-Ahead Log
+    #[test]
+    fn wal_replay_and_torn_tail() {
+        let path = std::env::temp_dir().join(format!("wal-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let open = || DurableTransactionLog::<String>::open(&path, SyncPolicy::Every(2)).unwrap();
+        let contents = |wal: &DurableTransactionLog<String>| -> Vec<String> {
+            wal.iter().cloned().collect()
+        };
+
+        let mut wal = open();
+        assert!(wal.is_empty());
+        for command in ["One", "Two", "Three"] {
+            wal.append(command.to_string()).unwrap();
+        }
+        wal.sync().unwrap();
+        drop(wal);
+        let wal = open();
+        assert_eq!(contents(&wal), ["One", "Two", "Three"]);
+        assert_eq!(wal.torn_bytes(), 0);
+        drop(wal);
+
+        // A crash halfway through writing a record
+        let full_len = fs::metadata(&path).unwrap().len();
+        let torn = frame(b"Four");
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&torn[..torn.len() - 2]).unwrap();
+        drop(file);
+        let mut wal = open();
+        assert_eq!(wal.torn_bytes(), torn.len() as u64 - 2);
+        assert_eq!(fs::metadata(&path).unwrap().len(), full_len);
+        wal.append("Five".to_string()).unwrap();
+        drop(wal);
+        assert_eq!(contents(&open()), ["One", "Two", "Three", "Five"]);
+
+        // A flipped bit in the middle of the log is not a torn tail, so
+        // the file is left alone
+        let intact = fs::read(&path).unwrap();
+        let offset = frame(b"One").len();
+        let corrupt_at = |at: usize, mask: u8| {
+            let mut bytes = intact.clone();
+            bytes[at] ^= mask;
+            fs::write(&path, &bytes).unwrap();
+            match DurableTransactionLog::<String>::open(&path, SyncPolicy::Always) {
+                Err(WalError::Corrupt { offset: at }) => assert_eq!(at, offset as u64),
+                _ => panic!("the damaged record should be reported"),
+            }
+            assert_eq!(fs::read(&path).unwrap(), bytes);
+        };
+        corrupt_at(offset + frame(b"Two").len() - 1, 1);
+        // A length cutting the record short
+        corrupt_at(offset, 1);
+
+        // The same damage to the last record is a torn tail
+        let last = intact.len() - frame(b"Five").len();
+        let mut bytes = intact.clone();
+        bytes[last + 3] ^= 0x80;
+        fs::write(&path, &bytes).unwrap();
+        let wal = open();
+        assert_eq!(contents(&wal), ["One", "Two", "Three"]);
+        assert_eq!(fs::metadata(&path).unwrap().len(), last as u64);
+        drop(wal);
+
+        // A length running past the end of the file claims every record
+        // behind it, so even in the middle it reads as a torn tail
+        let mut bytes = intact.clone();
+        bytes[offset + 3] ^= 0x80;
+        fs::write(&path, &bytes).unwrap();
+        let wal = open();
+        assert_eq!(contents(&wal), ["One"]);
+        assert_eq!(wal.torn_bytes(), (intact.len() - offset) as u64);
+        drop(wal);
+
+        // A torn record whose payload happens to hold a valid record
+        let payload = [frame(b""), b"Six".to_vec()].concat();
+        let torn = frame(&payload);
+        fs::write(&path, frame(b"One")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&torn[..torn.len() - 1]).unwrap();
+        drop(file);
+        let wal = DurableTransactionLog::<Vec<u8>>::open(&path, SyncPolicy::Never).unwrap();
+        assert_eq!(wal.log().len(), 1);
+        assert_eq!(wal.torn_bytes(), torn.len() as u64 - 1);
+        drop(wal);
+
+        // A record that checks out but is not valid UTF-8
+        fs::write(&path, frame(b"One")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&frame(&[0xff, 0xfe])).unwrap();
+        drop(file);
+        let offset = frame(b"One").len() as u64;
+        match DurableTransactionLog::<String>::open(&path, SyncPolicy::Always) {
+            Err(WalError::Corrupt { offset: at }) => assert_eq!(at, offset),
+            _ => panic!("the record should not decode"),
+        }
+        let wal = DurableTransactionLog::<Vec<u8>>::open(&path, SyncPolicy::Never).unwrap();
+        assert_eq!(wal.log().len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    // Segmented Log
+    #[test]
+    fn segmented_log_roll_and_retention() {
+        let entries = |log: &SegmentedLog<u64, u64>| -> Vec<(u64, u64)> {
+            log.iter().map(|(seq, value)| (seq, *value)).collect()
+        };
+        let roll = RollPolicy {
+            max_entries: Some(3),
+            max_bytes: None,
+        };
+        let mut log = SegmentedLog::<u64, u64>::new_empty(roll, Retention::Forever);
+        for i in 0..10 {
+            assert_eq!(log.append(i * 10), i);
+        }
+        assert_eq!(log.segment_count(), 4);
+        assert_eq!(log.len(), 10);
+        assert_eq!(entries(&log)[4], (4, 40));
+
+        // Within a segment and then across segments
+        log.truncate_before(1);
+        assert_eq!(log.first_seq(), 1);
+        log.truncate_before(7);
+        assert_eq!(log.segment_count(), 2);
+        assert_eq!(entries(&log), [(7, 70), (8, 80), (9, 90)]);
+
+        // The snapshot covers everything before the checkpoint
+        let total: u64 = log.iter().map(|(_, value)| *value).sum();
+        log.checkpoint(total);
+        assert!(log.is_empty());
+        assert_eq!(log.snapshot(), Some((10, &240)));
+        assert_eq!(log.append(5), 10);
+        assert_eq!(entries(&log), [(10, 5)]);
+
+        let roll = RollPolicy {
+            max_entries: None,
+            max_bytes: Some(10),
+        };
+        let mut log = SegmentedLog::<String>::new_empty(roll, Retention::Segments(2));
+        for command in ["hello", "world", "this one is too long", "a", "b"] {
+            log.append(command.to_string());
+        }
+        // [hello world] [this one is too long] [a b], the oldest retired
+        assert_eq!(log.segment_count(), 2);
+        assert_eq!(log.first_seq(), 2);
+        assert_eq!(log.len(), 3);
+        // Dropping "a" frees its byte, so nine more still fit next to "b"
+        log.truncate_before(4);
+        log.append("123456789".to_string());
+        assert_eq!(log.segment_count(), 1);
+        assert_eq!(log.len(), 2);
+
+        let roll = RollPolicy {
+            max_entries: Some(1),
+            max_bytes: None,
+        };
+        let age = Duration::from_secs(60);
+        let mut log = SegmentedLog::<u8>::new_empty(roll, Retention::MaxAge(age));
+        let start = Instant::now();
+        log.append(1);
+        log.append(2);
+        log.append(3);
+        assert_eq!(log.enforce_retention_at(start), 0);
+        assert_eq!(log.enforce_retention_at(start + age), 0);
+        // Only the closed segments age out, never the active one
+        assert_eq!(log.enforce_retention_at(start + 2 * age), 2);
+        assert_eq!(log.iter().map(|(seq, _)| seq).collect::<Vec<_>>(), [2]);
     }
 
     // Doubly Linked List DLL
@@ -78,7 +387,500 @@ mod test {
         assert_eq!(list.pop_fwd(), None);
     }
 
-    // Add test for DLL Iter
+    #[test]
+    fn dll_clone() {
+        let mut list = BetterTransactionLog::<&str>::new_empty();
+        list.append_bwd("One");
+        list.append_bwd("Two");
+
+        // Both copies can be emptied on their own
+        let mut copy = list.clone();
+        assert_eq!(copy.pop_fwd(), Some("One"));
+        copy.append_bwd("Three");
+        assert_eq!(list.pop_bwd(), Some("Two"));
+        assert_eq!(list.pop_bwd(), Some("One"));
+        assert_eq!(list.pop_bwd(), None);
+        assert_eq!(copy.pop_fwd(), Some("Two"));
+        assert_eq!(copy.pop_fwd(), Some("Three"));
+        assert_eq!(copy.length, 0);
+    }
+
+    #[test]
+    fn dll_cursor() {
+        let mut list: BetterTransactionLog<u8> = [1, 2, 4].into_iter().collect();
+        {
+            let mut cursor = list.cursor_front();
+            assert_eq!(cursor.index(), Some(0));
+            assert!(cursor.peek_prev().is_none());
+            cursor.move_next();
+            assert_eq!(*cursor.current().unwrap(), 2);
+            assert_eq!(*cursor.peek_next().unwrap().borrow(), 4);
+            cursor.move_next();
+            cursor.move_next();
+            // Past the tail sits the ghost position, which wraps around
+            assert_eq!(cursor.index(), None);
+            assert!(cursor.current().is_none());
+            assert_eq!(*cursor.peek_prev().unwrap().borrow(), 4);
+            assert_eq!(*cursor.peek_next().unwrap().borrow(), 1);
+            cursor.move_next();
+            assert_eq!(cursor.index(), Some(0));
+        }
+
+        {
+            let mut cursor = list.cursor_front_mut();
+            cursor.move_next();
+            cursor.insert_after(3);
+            cursor.insert_before(0);
+            assert_eq!(cursor.index(), Some(2));
+            assert_eq!(cursor.remove_current(), Some(2));
+            assert_eq!(*cursor.current().unwrap(), 3);
+            *cursor.current().unwrap() = 2;
+            assert_eq!(cursor.as_cursor().index(), Some(2));
+            cursor.move_prev();
+            cursor.move_prev();
+            cursor.move_prev();
+            cursor.insert_after(10);
+            cursor.insert_before(99);
+        }
+        assert_eq!(format!("{:?}", list), "[10, 1, 0, 2, 4, 99]");
+        assert_eq!(list.length, 6);
+
+        let (front, back) = {
+            let mut cursor = list.cursor_back_mut();
+            assert_eq!(cursor.remove_current(), Some(99));
+            assert_eq!(cursor.remove_current(), None);
+            cursor.move_prev();
+            cursor.move_prev();
+            let back = cursor.split_after();
+            let front = cursor.split_before();
+            assert_eq!(cursor.index(), Some(0));
+            assert_eq!(*cursor.current().unwrap(), 2);
+            (front, back)
+        };
+        assert_eq!(
+            format!("{:?} {:?} {:?}", front, list, back),
+            "[10, 1, 0] [2] [4]"
+        );
+        assert_eq!((front.length, list.length, back.length), (3, 1, 1));
+
+        assert_eq!(list.cursor_front_mut().remove_current(), Some(2));
+        assert!(list.peek_fwd().is_none() && list.peek_bwd().is_none());
+        list.cursor_back_mut().insert_before(7);
+        assert_eq!(list.pop_fwd(), Some(7));
+
+        // A cursor that is done with still holds its node until it goes out
+        // of scope, which does not stop the log from popping that node
+        list.extend([1, 2]);
+        let cursor = list.cursor_front();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(list.pop_fwd(), Some(1));
+        assert_eq!(list.pop_bwd(), Some(2));
+    }
+
+    #[test]
+    fn dll_iter() {
+        let mut list: BetterTransactionLog<u8> = (1..=5).collect();
+        assert_eq!(
+            list.iter().map(|v| *v.borrow()).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            list.iter_bwd().map(|v| *v.borrow()).collect::<Vec<_>>(),
+            [5, 4, 3, 2, 1]
+        );
+        assert_eq!(list.iter_fwd().len(), 5);
+        assert_eq!(list.length, 5);
+
+        // Both ends meet in the middle without handing out a value twice
+        let mut iter = list.iter();
+        assert_eq!(*iter.next().unwrap().borrow(), 1);
+        assert_eq!(*iter.next_back().unwrap().borrow(), 5);
+        assert_eq!(*iter.next().unwrap().borrow(), 2);
+        assert_eq!(*iter.next_back().unwrap().borrow(), 4);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(*iter.next().unwrap().borrow(), 3);
+        assert!(iter.next_back().is_none());
+        assert!(iter.next().is_none());
+
+        // Values handed out can be held on to while walking on
+        let held: Vec<_> = list.iter_mut().collect();
+        for mut value in held {
+            *value.borrow_mut() *= 10;
+        }
+        let mut iter = list.iter_mut();
+        let mut first = iter.next().unwrap();
+        let mut last = iter.next_back().unwrap();
+        *first.borrow_mut() += 1;
+        *last.borrow_mut() += 1;
+        assert_eq!(iter.count(), 3);
+        drop((first, last));
+        for mut value in &mut list {
+            *value.borrow_mut() += 1;
+        }
+        assert_eq!(format!("{:?}", list), "[12, 21, 31, 41, 52]");
+        // A handle that is done with does not keep its value in the log
+        let first = list.iter().next().unwrap();
+        assert_eq!(*first.borrow(), 12);
+        assert_eq!(list.pop_fwd(), Some(12));
+        list.append_fwd(12);
+
+        // Consuming does not need `Clone`
+        struct Token(u8);
+        let tokens: BetterTransactionLog<Token> = (1..=4).map(Token).collect();
+        let mut iter = tokens.into_iter();
+        assert_eq!(iter.next_back().map(|t| t.0), Some(4));
+        assert_eq!(iter.map(|t| t.0).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(list.into_iter().next_back(), Some(52));
+    }
+
+    // Command History
+    #[derive(Clone, Debug, PartialEq)]
+    enum Setting {
+        Rename { from: String, to: String },
+        Interval { from: u32, to: u32 },
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Config {
+        name: String,
+        interval: u32,
+    }
+
+    impl Command<Config> for Setting {
+        fn apply(&self, config: &mut Config) {
+            match self {
+                Setting::Rename { to, .. } => config.name = to.clone(),
+                Setting::Interval { to, .. } => config.interval = *to,
+            }
+        }
+
+        fn revert(&self, config: &mut Config) {
+            match self {
+                Setting::Rename { from, .. } => config.name = from.clone(),
+                Setting::Interval { from, .. } => config.interval = *from,
+            }
+        }
+    }
+
+    fn rename(from: &str, to: &str) -> Setting {
+        Setting::Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn command_history() {
+        let mut history = CommandHistory::new_empty(Config::default());
+        assert!(!history.undo());
+        assert!(!history.redo());
+
+        history.execute(rename("", "sensor"));
+        history.execute(Setting::Interval { from: 0, to: 30 });
+        history.execute(rename("sensor", "thermostat"));
+        assert_eq!(history.position(), 3);
+        assert_eq!(history.state().name, "thermostat");
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(history.position(), 1);
+        assert_eq!(history.state().name, "sensor");
+        assert_eq!(history.state().interval, 0);
+        assert!(history.can_redo());
+
+        assert!(history.redo());
+        assert_eq!(history.state().interval, 30);
+
+        // A new command replaces the undone rename
+        history.execute(Setting::Interval { from: 30, to: 60 });
+        assert!(!history.can_redo());
+        assert_eq!(history.len(), 3);
+
+        history.seek(0);
+        assert_eq!(history.state(), &Config::default());
+        assert!(!history.can_undo());
+        history.seek(usize::MAX);
+        assert_eq!(history.position(), 3);
+        assert_eq!(history.state().interval, 60);
+        history.seek(2);
+        assert_eq!(history.state().interval, 30);
+        assert!(history.undo() && history.undo());
+        assert_eq!(history.state().name, "");
+        history.replay();
+        assert_eq!(history.state().interval, 60);
+
+        // The log replays onto a fresh state
+        let (log, config) = history.into_parts();
+        let mut replayed = CommandHistory::from_log(log.clone(), Config::default());
+        assert_eq!(replayed.position(), 0);
+        replayed.replay();
+        assert_eq!(replayed.state(), &config);
+        assert_eq!(log.iter_fwd().count(), 3);
+    }
+
+    #[test]
+    fn dll_append_split_splice_reverse() {
+        let values = |log: &BetterTransactionLog<u8>| {
+            log.iter().map(|v| *v.borrow()).collect::<Vec<_>>()
+        };
+        let mut log: BetterTransactionLog<u8> = (1..=3).collect();
+        let mut other: BetterTransactionLog<u8> = (4..=7).collect();
+        log.append_log(&mut other);
+        assert!(other.peek_fwd().is_none() && other.peek_bwd().is_none());
+        assert_eq!((log.length, other.length), (7, 0));
+        log.append_log(&mut other);
+        other.append_log(&mut log);
+        log.append_log(&mut other);
+        assert_eq!(values(&log), [1, 2, 3, 4, 5, 6, 7]);
+
+        // Cuts near either end
+        let mut rest = log.split_off(5);
+        assert_eq!(values(&rest), [6, 7]);
+        let mut middle = log.split_off(1);
+        assert_eq!(values(&middle), [2, 3, 4, 5]);
+        assert_eq!((log.length, middle.length, rest.length), (1, 4, 2));
+        assert_eq!(rest.pop_bwd(), Some(7));
+        assert_eq!(middle.pop_fwd(), Some(2));
+        assert!(log.split_off(1).peek_fwd().is_none());
+
+        log.splice(1, &mut middle);
+        log.splice(0, &mut rest);
+        assert_eq!(values(&log), [6, 1, 3, 4, 5]);
+        {
+            let mut cursor = log.cursor_front_mut();
+            cursor.move_next();
+            let mut merged: BetterTransactionLog<u8> = (10..=11).collect();
+            cursor.splice_before(&mut merged);
+            assert_eq!(cursor.index(), Some(3));
+            let mut merged: BetterTransactionLog<u8> = (20..=21).collect();
+            cursor.splice_after(&mut merged);
+            assert_eq!(*cursor.peek_next().unwrap().borrow(), 20);
+        }
+        assert_eq!(values(&log), [6, 10, 11, 1, 20, 21, 3, 4, 5]);
+        assert_eq!(log.length, 9);
+
+        log.reverse();
+        assert_eq!(values(&log), [5, 4, 3, 21, 20, 1, 11, 10, 6]);
+        assert_eq!(log.iter_bwd().map(|v| *v.borrow()).last(), Some(5));
+        assert_eq!(log.pop_bwd(), Some(6));
+        assert_eq!(log.pop_fwd(), Some(5));
+
+        // Splicing at the length appends
+        let mut tail: BetterTransactionLog<u8> = (7..=8).collect();
+        let length = log.len();
+        log.splice(length, &mut tail);
+        assert_eq!(values(&log), [4, 3, 21, 20, 1, 11, 10, 7, 8]);
+    }
+
+    #[test]
+    fn dll_drop_frees_nodes() {
+        // The nodes link each other both ways, so only `Drop` can free them
+        let tracker = Rc::new(());
+        let mut log: BetterTransactionLog<Rc<()>> = (0..4).map(|_| Rc::clone(&tracker)).collect();
+        let copy = log.clone();
+        let rest = log.split_off(2);
+        assert_eq!(Rc::strong_count(&tracker), 9);
+        drop((log, copy, rest));
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        struct Counted(Rc<()>);
+        impl Command<()> for Counted {
+            fn apply(&self, _: &mut ()) {}
+            fn revert(&self, _: &mut ()) {}
+        }
+        let mut history = CommandHistory::new_empty(());
+        for _ in 0..3 {
+            history.execute(Counted(Rc::clone(&tracker)));
+        }
+        assert!(history.undo());
+        assert_eq!(Rc::strong_count(&tracker), 4);
+        drop(history);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    // Arena Doubly Linked List
+    #[test]
+    fn arena_dll() {
+        let mut list = ArenaTransactionLog::<&str>::new_empty();
+        assert_eq!(list.pop_bwd(), None);
+        assert_eq!(list.pop_fwd(), None);
+        assert!(list.peek_fwd().is_none());
+
+        list.append_bwd("Three");
+        list.append_fwd("Two");
+        list.append_bwd("Four");
+        list.append_fwd("One");
+        assert_eq!(list.peek_fwd(), Some(&"One"));
+        assert_eq!(list.peek_bwd(), Some(&"Four"));
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            ["One", "Two", "Three", "Four"]
+        );
+        assert_eq!(list.iter_bwd().count(), 4);
+
+        assert_eq!(list.pop_bwd(), Some("Four"));
+        assert_eq!(list.pop_fwd(), Some("One"));
+        assert_eq!(list.pop_bwd(), Some("Three"));
+        assert_eq!(list.pop_fwd(), Some("Two"));
+        assert_eq!(list.pop_bwd(), None);
+        assert_eq!(list.length, 0);
+    }
+
+    #[test]
+    fn arena_dll_cursor_and_iter() {
+        let mut list: ArenaTransactionLog<u8> = [1, 2, 4].into_iter().collect();
+        let mut cursor = list.cursor_front();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&2));
+        assert_eq!(cursor.peek_next(), Some(&4));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&4));
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.insert_after(3);
+        cursor.insert_before(0);
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(cursor.remove_current(), Some(2));
+        *cursor.current().unwrap() = 2;
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.insert_after(10);
+        cursor.insert_before(99);
+        assert_eq!(format!("{:?}", list), "[10, 1, 0, 2, 4, 99]");
+
+        let mut cursor = list.cursor_back_mut();
+        cursor.move_prev();
+        cursor.move_prev();
+        let back = cursor.split_after();
+        let front = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(
+            format!("{:?} {:?} {:?}", front, list, back),
+            "[10, 1, 0] [2] [4, 99]"
+        );
+        assert_eq!((front.length, list.length, back.length), (3, 1, 2));
+
+        let mut list: ArenaTransactionLog<u8> = front.into_iter().chain(back).collect();
+        for value in &mut list {
+            *value += 1;
+        }
+        let mut iter = list.iter_mut();
+        *iter.next_back().unwrap() += 1;
+        assert_eq!(iter.len(), 4);
+        let mut iter = list.iter();
+        assert_eq!(iter.next_back(), Some(&101));
+        assert_eq!(iter.next(), Some(&11));
+        assert_eq!(iter.rev().copied().collect::<Vec<_>>(), [5, 1, 2]);
+        assert_eq!(list.into_iter().next_back(), Some(101));
+    }
+
+    #[test]
+    fn arena_dll_reuses_holes() {
+        let mut list: ArenaTransactionLog<u8> = (1..=5).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        // The new values land in the holes, out of order with their slots
+        cursor.insert_before(40);
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.insert_before(20);
+        cursor.move_next();
+        cursor.insert_after(30);
+        assert_eq!(format!("{:?}", list), "[1, 20, 3, 40, 30, 5]");
+        assert_eq!(
+            list.iter().rev().copied().collect::<Vec<_>>(),
+            [5, 30, 40, 3, 20, 1]
+        );
+        for value in list.iter_mut().rev() {
+            *value += 1;
+        }
+        assert_eq!(list.len(), 6);
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let back = cursor.split_after();
+        let front = cursor.split_before();
+        assert_eq!(
+            format!("{:?} {:?} {:?}", front, list, back),
+            "[2, 21] [4] [41, 31, 6]"
+        );
+        assert_eq!(back.iter().rev().copied().collect::<Vec<_>>(), [6, 31, 41]);
+        list.append_bwd(5);
+        list.append_fwd(3);
+        assert_eq!(format!("{:?}", list), "[3, 4, 5]");
+    }
+
+    #[test]
+    fn arena_dll_append_split_splice_reverse() {
+        let values = |log: &ArenaTransactionLog<u8>| log.iter().copied().collect::<Vec<_>>();
+        let mut log: ArenaTransactionLog<u8> = (1..=3).collect();
+        let mut other: ArenaTransactionLog<u8> = (4..=7).collect();
+        log.append_log(&mut other);
+        assert!(other.peek_fwd().is_none() && other.peek_bwd().is_none());
+        assert_eq!((log.length, other.length), (7, 0));
+        log.append_log(&mut other);
+        other.append_log(&mut log);
+        log.append_log(&mut other);
+        assert_eq!(values(&log), [1, 2, 3, 4, 5, 6, 7]);
+
+        let mut rest = log.split_off(5);
+        let mut middle = log.split_off(1);
+        assert_eq!(values(&middle), [2, 3, 4, 5]);
+        assert_eq!((log.length, middle.length, rest.length), (1, 4, 2));
+        assert_eq!(rest.pop_bwd(), Some(7));
+        assert_eq!(middle.pop_fwd(), Some(2));
+        assert!(log.split_off(1).is_empty());
+
+        log.splice(1, &mut middle);
+        log.splice(0, &mut rest);
+        assert_eq!(values(&log), [6, 1, 3, 4, 5]);
+        let mut cursor = log.cursor_front_mut();
+        cursor.move_next();
+        let mut merged: ArenaTransactionLog<u8> = (10..=11).collect();
+        cursor.splice_before(&mut merged);
+        assert_eq!(cursor.index(), Some(3));
+        let mut merged: ArenaTransactionLog<u8> = (20..=21).collect();
+        cursor.splice_after(&mut merged);
+        assert_eq!(cursor.peek_next(), Some(&20));
+        assert!(merged.is_empty());
+        assert_eq!(values(&log), [6, 10, 11, 1, 20, 21, 3, 4, 5]);
+
+        log.reverse();
+        assert_eq!(values(&log), [5, 4, 3, 21, 20, 1, 11, 10, 6]);
+        assert_eq!(log.iter_bwd().next(), Some(&6));
+        let mut tail: ArenaTransactionLog<u8> = (7..=8).collect();
+        let length = log.len();
+        log.splice(length, &mut tail);
+        assert_eq!(log.pop_bwd(), Some(8));
+        assert_eq!(log.pop_fwd(), Some(5));
+        assert_eq!(log.length, 9);
+    }
+
+    #[test]
+    fn arena_dll_frees_everything() {
+        fn assert_send<S: Send>(_: &S) {}
+
+        let counter = std::sync::Arc::new(());
+        let mut list: ArenaTransactionLog<_> = (0..10).map(|_| counter.clone()).collect();
+        let mut cursor = list.cursor_front_mut();
+        cursor.remove_current();
+        cursor.move_next();
+        cursor.insert_after(counter.clone());
+        drop(cursor.split_after());
+        assert_eq!(std::sync::Arc::strong_count(&counter), 3);
+        assert_send(&list);
+        std::thread::spawn(move || list.clear()).join().unwrap();
+        assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+    }
 
     // Skip List
     #[test]
@@ -100,6 +902,78 @@ mod test {
         assert_eq!(list.find(18), None);
     }
 
+    #[test]
+    fn sl_single_level() {
+        // Every level of the head is linked, so the search starts at the top
+        let mut list = BestTransactionLog::<usize, &str>::new_empty(0);
+        list.append(1, "One");
+        list.append(2, "Two");
+
+        assert_eq!(list.find(1), Some("One"));
+        assert_eq!(list.find(2), Some("Two"));
+        assert_eq!(list.find(3), None);
+    }
+
+    #[test]
+    fn sl_insert_out_of_order() {
+        let mut keys: Vec<usize> = (0..200).map(|k| k * 2).collect();
+        keys.shuffle(&mut rand::thread_rng());
+        let mut list = BestTransactionLog::<usize, usize>::new_empty(6);
+        for key in keys.iter() {
+            assert_eq!(list.insert(*key, key + 1), None);
+        }
+        assert_eq!(list.length, 200);
+        assert_eq!(list.insert(10, 0), Some(11));
+        assert_eq!(list.find(10), Some(0));
+        for key in keys.iter().filter(|k| **k != 10) {
+            assert_eq!(list.find(*key), Some(key + 1));
+            assert_eq!(list.find(key + 1), None);
+        }
+
+        // Appending still works after inserts, the tails have kept up
+        list.append(1000, 1);
+        assert_eq!(list.find(1000), Some(1));
+        let ordered: Vec<usize> = list.into_iter().map(|(k, _)| k).collect();
+        let mut expected: Vec<usize> = (0..200).map(|k| k * 2).collect();
+        expected.push(1000);
+        assert_eq!(ordered, expected);
+    }
+
+    #[test]
+    fn sl_insert_edge_cases() {
+        for max_level in [0, 3] {
+            let mut list = BestTransactionLog::<usize, &str>::new_empty(max_level);
+            // Into an empty list
+            assert_eq!(list.insert(5, "Five"), None);
+            assert_eq!(list.find(5), Some("Five"));
+            // Onto the head's key
+            assert_eq!(list.insert(5, "Fuenf"), Some("Five"));
+            assert_eq!(list.length, 1);
+
+            // Before the head, which takes over the head node
+            assert_eq!(list.insert(2, "Two"), None);
+            assert_eq!(list.insert(1, "One"), None);
+            assert_eq!(list.find(1), Some("One"));
+            assert_eq!(list.find(2), Some("Two"));
+            assert_eq!(list.find(5), Some("Fuenf"));
+            // Onto a key that used to be the head
+            assert_eq!(list.insert(2, "Zwei"), Some("Two"));
+            assert_eq!(list.length, 3);
+
+            // Past the tail, after which appending still works
+            assert_eq!(list.insert(9, "Nine"), None);
+            list.append(10, "Ten");
+            assert_eq!(list.find(9), Some("Nine"));
+            assert_eq!(list.find(10), Some("Ten"));
+            assert_eq!(list.length, 5);
+            let ordered: Vec<(usize, &str)> = list.into_iter().collect();
+            assert_eq!(
+                ordered,
+                vec![(1, "One"), (2, "Zwei"), (5, "Fuenf"), (9, "Nine"), (10, "Ten")]
+            );
+        }
+    }
+
     // Dynamic Array
     #[test]
     fn da() {
@@ -134,7 +1008,7 @@ mod test {
         let len = 10;
         let mut tree = DeviceRegistry::<usize, String>::new_empty();
         let mut items: Vec<IoTDevice<usize, String>> =
-            (0..len).map(|id| new_device_with_id(id)).collect();
+            (0..len).map(new_device_with_id).collect();
 
         let mut rng = rand::thread_rng();
         items.shuffle(&mut rng);
@@ -148,9 +1022,871 @@ mod test {
         let v: RefCell<Vec<IoTDevice<usize, String>>> = RefCell::new(Vec::new());
         tree.walk(|n| v.borrow_mut().push(n.clone()));
 
-        let mut items = items;
-
-        items.sort_by(|a, b| b.id.cmp(&a.id));
+        items.sort_by_key(|item| item.id);
+        items.reverse();
         assert_eq!(v.into_inner(), items);
     }
+
+    #[test]
+    fn bst_remove() {
+        let mut tree = DeviceRegistry::<usize, &str>::new_empty();
+        assert_eq!(tree.remove(1), None);
+
+        // 2 has two children, 3 has one and 1 is a leaf
+        for id in [2, 1, 3, 4] {
+            tree.add(IoTDevice::new(id, "Add", "Path"));
+        }
+        assert_eq!(tree.remove(2), Some(IoTDevice::new(2, "Add", "Path")));
+        assert_eq!(tree.remove(3), Some(IoTDevice::new(3, "Add", "Path")));
+        assert_eq!(tree.remove(1), Some(IoTDevice::new(1, "Add", "Path")));
+        assert_eq!(tree.remove(1), None);
+        assert_eq!(tree.length, 1);
+        assert_eq!(tree.find(4), Some(IoTDevice::new(4, "Add", "Path")));
+        assert_eq!(tree.remove(4), Some(IoTDevice::new(4, "Add", "Path")));
+        assert_eq!(tree.length, 0);
+        assert_eq!(tree.find(4), None);
+    }
+
+    #[test]
+    fn bst_remove_shuffled() {
+        let len = 200;
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..10 {
+            let mut tree = DeviceRegistry::<usize, String>::new_empty();
+            let mut ids: Vec<usize> = (0..len).collect();
+            ids.shuffle(&mut rng);
+            for id in ids.iter() {
+                tree.add(new_device_with_id(*id));
+            }
+
+            ids.shuffle(&mut rng);
+            let (removed, kept) = ids.split_at(len / 2);
+            for id in removed {
+                assert_eq!(tree.remove(*id), Some(new_device_with_id(*id)));
+            }
+            assert_eq!(tree.length, kept.len());
+            for id in removed {
+                assert_eq!(tree.find(*id), None);
+            }
+
+            let v: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+            tree.walk(|n| v.borrow_mut().push(n.id));
+            let mut kept = kept.to_vec();
+            kept.sort_by_key(|id| Reverse(*id));
+            assert_eq!(v.into_inner(), kept);
+        }
+    }
+
+    #[test]
+    fn bst_iter() {
+        let len = 10;
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        let mut ids: Vec<usize> = (0..len).collect();
+        ids.shuffle(&mut rand::thread_rng());
+        for id in ids {
+            tree.add(new_device_with_id(id));
+        }
+
+        let descending: Vec<usize> = (0..len).rev().collect();
+        assert_eq!(tree.iter().map(|d| d.id).collect::<Vec<_>>(), descending);
+        assert_eq!(
+            tree.iter().rev().map(|d| d.id).collect::<Vec<_>>(),
+            (0..len).collect::<Vec<_>>()
+        );
+
+        // Both ends meet in the middle without repeating a device
+        let mut iter = tree.iter();
+        let mut seen = Vec::new();
+        while let (Some(front), back) = (iter.next(), iter.next_back()) {
+            seen.push(front.id);
+            seen.extend(back.map(|d| d.id));
+        }
+        seen.sort();
+        assert_eq!(seen, (0..len).collect::<Vec<_>>());
+
+        for dev in tree.iter_mut() {
+            dev.path = format!("Moved {}", dev.id);
+        }
+        assert_eq!(tree.find(3).unwrap().path, "Moved 3");
+
+        let owned: Vec<IoTDevice<usize, String>> = tree.into_iter().rev().collect();
+        assert_eq!(owned.len(), len);
+        assert_eq!(owned[0].id, 0);
+        assert_eq!(owned[len - 1].path, format!("Moved {}", len - 1));
+    }
+
+    #[test]
+    fn bst_iter_degenerate() {
+        // Sorted ids turn the tree into a list
+        let len = 1000;
+        let mut tree = DeviceRegistry::<usize, &str>::new_empty();
+        for id in 0..len {
+            tree.add(IoTDevice::new(id, "Add", "Path"));
+        }
+        assert!(tree.iter().map(|d| d.id).eq((0..len).rev()));
+        assert!(tree.iter().rev().map(|d| d.id).eq(0..len));
+    }
+
+    #[test]
+    fn bst_range_and_neighbours() {
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        assert_eq!(tree.min(), None);
+        assert_eq!(tree.max(), None);
+        assert_eq!(tree.range(..).next(), None);
+
+        // Only even ids, so odd ones exercise the gaps
+        let mut ids: Vec<usize> = (0..50).map(|id| id * 2).collect();
+        ids.shuffle(&mut rand::thread_rng());
+        for id in ids {
+            tree.add(new_device_with_id(id));
+        }
+
+        let ids_of = |devs: Vec<&IoTDevice<usize, String>>| -> Vec<usize> {
+            devs.iter().map(|d| d.id).collect()
+        };
+        assert_eq!(ids_of(tree.range(10..=16).collect()), vec![16, 14, 12, 10]);
+        assert_eq!(ids_of(tree.range(9..16).rev().collect()), vec![10, 12, 14]);
+        assert_eq!(
+            ids_of(
+                tree.range((Bound::Excluded(94), Bound::Unbounded))
+                    .collect()
+            ),
+            vec![98, 96]
+        );
+        assert_eq!(tree.range(..4).count(), 2);
+        assert_eq!(tree.range(200..).count(), 0);
+
+        assert_eq!(tree.min().map(|d| d.id), Some(0));
+        assert_eq!(tree.max().map(|d| d.id), Some(98));
+        assert_eq!(tree.floor(11).map(|d| d.id), Some(10));
+        assert_eq!(tree.floor(12).map(|d| d.id), Some(12));
+        assert_eq!(tree.ceiling(11).map(|d| d.id), Some(12));
+        assert_eq!(tree.ceiling(12).map(|d| d.id), Some(12));
+        assert_eq!(tree.successor(12).map(|d| d.id), Some(14));
+        assert_eq!(tree.predecessor(12).map(|d| d.id), Some(10));
+        assert_eq!(tree.predecessor(0), None);
+        assert_eq!(tree.successor(98), None);
+        assert_eq!(tree.floor(200).map(|d| d.id), Some(98));
+    }
+
+    #[test]
+    fn bst_order_statistics() {
+        let len = 100;
+        let mut rng = rand::thread_rng();
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        assert_eq!(tree.select(0), None);
+        assert_eq!(tree.rank(5), 0);
+
+        let mut ids: Vec<usize> = (0..len).map(|id| id * 10).collect();
+        ids.shuffle(&mut rng);
+        for id in ids.iter() {
+            tree.add(new_device_with_id(*id));
+        }
+        for k in 0..len {
+            assert_eq!(tree.select(k).map(|d| d.id), Some(k * 10));
+            assert_eq!(tree.rank(k * 10), k);
+            assert_eq!(tree.rank(k * 10 + 1), k + 1);
+        }
+        assert_eq!(tree.select(len), None);
+
+        ids.shuffle(&mut rng);
+        for id in ids.iter().take(len / 2) {
+            tree.remove(*id);
+        }
+        let mut kept: Vec<usize> = ids[len / 2..].to_vec();
+        kept.sort();
+        for (k, id) in kept.iter().enumerate() {
+            assert_eq!(tree.select(k).map(|d| d.id), Some(*id));
+            assert_eq!(tree.rank(*id), k);
+        }
+        assert_eq!(tree.select(kept.len()), None);
+    }
+
+    #[test]
+    fn bst_insert_and_entry() {
+        let mut tree = DeviceRegistry::<usize, &str>::new_empty();
+        assert_eq!(tree.insert(IoTDevice::new(1, "One Add", "One Path")), None);
+        assert_eq!(
+            tree.insert(IoTDevice::new(1, "New Add", "New Path")),
+            Some(IoTDevice::new(1, "One Add", "One Path"))
+        );
+        tree.add(IoTDevice::new(1, "Newer Add", "Newer Path"));
+        assert_eq!(tree.length, 1);
+        assert_eq!(tree.iter().count(), 1);
+
+        assert_eq!(
+            tree.try_insert(IoTDevice::new(1, "Other Add", "Other Path")),
+            Err(IoTDevice::new(1, "Other Add", "Other Path"))
+        );
+        assert_eq!(
+            tree.try_insert(IoTDevice::new(2, "Two Add", "Two Path")),
+            Ok(())
+        );
+        assert_eq!(
+            tree.find(1),
+            Some(IoTDevice::new(1, "Newer Add", "Newer Path"))
+        );
+        assert_eq!(tree.length, 2);
+
+        tree.entry(3).or_insert("Three Add", "Three Path");
+        tree.entry(3)
+            .and_modify(|d| d.path = "Modified Path")
+            .or_insert("Unused Add", "Unused Path");
+        assert_eq!(tree.find(3).unwrap().path, "Modified Path");
+        assert_eq!(*tree.entry(4).key(), 4);
+        assert_eq!(tree.length, 3);
+
+        match tree.entry(2) {
+            Entry::Occupied(e) => {
+                assert_eq!(e.get().address, "Two Add");
+                assert_eq!(e.remove(), IoTDevice::new(2, "Two Add", "Two Path"));
+            }
+            Entry::Vacant(_) => panic!("device 2 should be registered"),
+        }
+        assert!(matches!(tree.entry(2), Entry::Vacant(_)));
+        assert_eq!(tree.length, 2);
+    }
+
+    #[test]
+    fn bst_traversal_orders() {
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        assert_eq!(tree.pre_order().next(), None);
+
+        // Larger ids go left: 4 has 6 (with 7, 5) and 2 (with 3, 1) below it
+        for id in [4, 2, 6, 1, 3, 5, 7] {
+            tree.add(new_device_with_id(id));
+        }
+        let ids = |devs: Vec<&IoTDevice<usize, String>>| -> Vec<usize> {
+            devs.iter().map(|d| d.id).collect()
+        };
+        assert_eq!(ids(tree.pre_order().collect()), vec![4, 6, 7, 5, 2, 3, 1]);
+        assert_eq!(ids(tree.post_order().collect()), vec![7, 5, 6, 3, 1, 2, 4]);
+        assert_eq!(ids(tree.level_order().collect()), vec![4, 6, 2, 7, 5, 3, 1]);
+
+        // Replaying the pre-order rebuilds the same shape
+        let mut shuffled: Vec<usize> = (0..100).collect();
+        shuffled.shuffle(&mut rand::thread_rng());
+        let mut original = DeviceRegistry::<usize, String>::new_empty();
+        for id in shuffled {
+            original.add(new_device_with_id(id));
+        }
+        let mut copy = DeviceRegistry::<usize, String>::new_empty();
+        for dev in original.pre_order() {
+            copy.add(dev.clone());
+        }
+        assert_eq!(
+            ids(copy.level_order().collect()),
+            ids(original.level_order().collect())
+        );
+    }
+
+    #[test]
+    fn bst_shape_diagnostics() {
+        let mut tree = DeviceRegistry::<usize, &str>::new_empty();
+        assert_eq!(tree.height(), 0);
+        assert!(tree.is_balanced());
+        assert_eq!(tree.to_dot(), "digraph DeviceRegistry {\n}\n");
+
+        for id in [4, 2, 6, 1, 3, 5, 7] {
+            tree.add(IoTDevice::new(id, "Add", "Path"));
+        }
+        assert_eq!(tree.height(), 3);
+        assert_eq!(tree.depth_histogram(), vec![1, 2, 4]);
+        assert!(tree.is_balanced());
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph DeviceRegistry {\n"));
+        assert!(dot.contains("    n0 [label=\"4\"];\n"));
+        assert!(dot.contains("    n0 -> n1 [label=\"left\"];\n"));
+        assert!(dot.contains("    n1 [label=\"6\"];\n"));
+        assert_eq!(dot.matches("->").count(), 6);
+
+        // Sorted ids degenerate into a list
+        let mut list = DeviceRegistry::<usize, &str>::new_empty();
+        for id in 0..1000 {
+            list.add(IoTDevice::new(id, "Add", "Path"));
+        }
+        assert_eq!(list.height(), 1000);
+        assert_eq!(list.depth_histogram(), vec![1; 1000]);
+        assert!(!list.is_balanced());
+    }
+
+    #[test]
+    fn bst_rebalance() {
+        let len = 1000;
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        tree.rebalance();
+        assert_eq!(tree.height(), 0);
+
+        for id in 0..len {
+            tree.add(new_device_with_id(id));
+        }
+        let device_at = |tree: &DeviceRegistry<usize, String>| -> *const IoTDevice<usize, String> {
+            tree.iter().find(|d| d.id == 500).unwrap()
+        };
+        let before = device_at(&tree);
+        assert_eq!(tree.height(), len);
+        tree.rebalance();
+        // Nodes are relinked, not reallocated
+        assert_eq!(device_at(&tree), before);
+        assert_eq!(tree.height(), 10);
+        assert!(tree.is_balanced());
+        assert_eq!(tree.length, len);
+        assert!(tree.iter().map(|d| d.id).eq((0..len).rev()));
+        for k in 0..len {
+            assert_eq!(tree.select(k).map(|d| d.id), Some(k));
+        }
+
+        let tree = DeviceRegistry::<usize, String>::from_sorted(
+            (0..len).map(new_device_with_id).collect(),
+        );
+        assert_eq!(tree.height(), 10);
+        assert!(tree.is_balanced());
+        assert_eq!(tree.length, len);
+        assert_eq!(tree.find(500), Some(new_device_with_id(500)));
+        assert_eq!(tree.rank(500), 500);
+
+        // Unsorted input is sorted, the last device with an id wins
+        let mut devices: Vec<IoTDevice<usize, String>> =
+            (0..len).rev().map(new_device_with_id).collect();
+        devices.push(IoTDevice::new(500, "Replaced", "Path"));
+        let tree = DeviceRegistry::from_sorted(devices);
+        assert!(tree.is_balanced());
+        assert_eq!(tree.length, len);
+        assert!(tree.iter().map(|d| d.id).eq((0..len).rev()));
+        assert_eq!(tree.find(500).unwrap().address, "Replaced");
+    }
+
+    #[test]
+    fn typed_device() {
+        let device = TypedIoTDevice::new(1, "10.0.0.1", "/dev/one", DeviceKind::Sensor).unwrap();
+        assert_eq!(device.address, IpAddr::from([10, 0, 0, 1]));
+        assert!(TypedIoTDevice::new(1, "::1", "/dev/one", DeviceKind::Camera).is_ok());
+        assert!(matches!(
+            TypedIoTDevice::new(1, "10.0.0.256", "/dev/one", DeviceKind::Sensor),
+            Err(DeviceError::InvalidAddress(_, _))
+        ));
+        assert_eq!(
+            TypedIoTDevice::new(1, "10.0.0.1", "", DeviceKind::Sensor),
+            Err(DeviceError::EmptyPath)
+        );
+
+        assert_eq!("Gateway".parse(), Ok(DeviceKind::Gateway));
+        assert_eq!(
+            "toaster".parse::<DeviceKind>(),
+            Err(DeviceError::InvalidKind("toaster".to_string()))
+        );
+
+        let generic: IoTDevice<usize, String> = IoTDevice::new(2, "192.168.1.2", "/dev/two");
+        let typed = TypedIoTDevice::try_from(generic.clone()).unwrap();
+        assert_eq!(typed.kind, DeviceKind::Unknown);
+        assert_eq!(IoTDevice::from(typed), generic);
+        assert!(TypedIoTDevice::try_from(new_device_with_id::<usize, String>(3)).is_err());
+
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        assert_eq!(tree.insert_checked(generic), Ok(None));
+        assert!(tree.insert_checked(new_device_with_id(3)).is_err());
+        assert_eq!(tree.length, 1);
+    }
+
+    #[test]
+    fn bst_secondary_indexes() {
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        tree.add(IoTDevice::new(1, "10.0.0.1", "/site/a/one"));
+        tree.enable_indexes();
+        tree.add(IoTDevice::new(2, "10.0.0.2", "/site/a/two"));
+        tree.add(IoTDevice::new(3, "10.0.0.2", "/site/b/three"));
+
+        let ids = |devs: Vec<&IoTDevice<usize, String>>| -> Vec<usize> {
+            let mut ids: Vec<usize> = devs.iter().map(|d| d.id).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            ids(tree.find_by_address(&"10.0.0.2".to_string())),
+            vec![2, 3]
+        );
+        assert_eq!(ids(tree.find_by_path_prefix("/site/a/")), vec![1, 2]);
+        assert_eq!(ids(tree.find_by_path_prefix("/site/")), vec![1, 2, 3]);
+        assert_eq!(ids(tree.find_by_path_prefix("/site/c")), vec![]);
+
+        // Replacing and removing keep the indexes in step
+        tree.insert(IoTDevice::new(2, "10.0.0.9", "/site/c/two"));
+        tree.remove(1);
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.2".to_string())), vec![3]);
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.9".to_string())), vec![2]);
+        assert_eq!(ids(tree.find_by_path_prefix("/site/a/")), vec![]);
+        assert_eq!(ids(tree.find_by_path_prefix("/site/c/")), vec![2]);
+
+        // So do updates through entries and iter_mut
+        tree.entry(3)
+            .and_modify(|d| d.address = "10.0.0.3".to_string());
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.2".to_string())), vec![]);
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.3".to_string())), vec![3]);
+        for dev in tree.iter_mut() {
+            dev.path = format!("/moved/{}", dev.id);
+        }
+        assert_eq!(ids(tree.find_by_path_prefix("/moved/")), vec![2, 3]);
+        assert_eq!(ids(tree.find_by_path_prefix("/site/")), vec![]);
+        // The next change rebuilds the indexes
+        tree.insert(IoTDevice::new(4, "10.0.0.4", "/moved/4"));
+        assert_eq!(ids(tree.find_by_path_prefix("/moved/")), vec![2, 3, 4]);
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.3".to_string())), vec![3]);
+        tree.remove(4);
+
+        tree.disable_indexes();
+        assert_eq!(ids(tree.find_by_address(&"10.0.0.9".to_string())), vec![2]);
+        assert_eq!(ids(tree.find_by_path_prefix("/moved/")), vec![2, 3]);
+
+        // Without indexes, addresses and paths need not be ordered
+        fn assert_sync<T: Sync>(_: &T) {}
+        assert_sync(&tree);
+        let mut unordered = DeviceRegistry::<usize, f64>::new_empty();
+        unordered.add(IoTDevice::new(1, 0.5, 1.5));
+        unordered.entry(1).and_modify(|d| d.path = 2.5);
+        assert_eq!(unordered.find(1).map(|d| d.path), Some(2.5));
+    }
+
+    #[test]
+    fn bst_persistence_round_trip() {
+        let mut tree = DeviceRegistry::<usize, String>::new_empty();
+        tree.add(IoTDevice::new(1, "10.0.0.1", "/plain"));
+        tree.add(IoTDevice::new(2, "a,b", "say \"hi\""));
+        tree.add(IoTDevice::new(3, "multi\nline\r\n", "tab\tand \\ back"));
+        tree.add(IoTDevice::new(4, "", "ünïcødé ✓ \u{1}"));
+
+        let devices = |t: &DeviceRegistry<usize, String>| -> Vec<(usize, String, String)> {
+            t.iter()
+                .map(|d| (d.id, d.address.clone(), d.path.clone()))
+                .collect()
+        };
+
+        let csv = tree.to_csv();
+        assert!(csv.starts_with("id,address,path\n4,,"));
+        let from_csv = DeviceRegistry::<usize, String>::from_csv(&csv).unwrap();
+        assert_eq!(devices(&from_csv), devices(&tree));
+        assert_eq!(from_csv.to_csv(), csv);
+
+        let json = tree.to_json();
+        assert!(json.contains(r#"{"id": 2, "address": "a,b", "path": "say \"hi\""}"#));
+        let from_json = DeviceRegistry::<usize, String>::from_json(&json).unwrap();
+        assert_eq!(devices(&from_json), devices(&tree));
+        assert_eq!(from_json.to_json(), json);
+
+        // Non-numeric ids are written as JSON strings
+        let mut named = DeviceRegistry::<String, String>::new_empty();
+        named.add(IoTDevice::new("pump-1".to_string(), "10.0.0.1", "/pump"));
+        let json = named.to_json();
+        assert!(json.contains("\"id\": \"pump-1\""));
+        let named = DeviceRegistry::<String, String>::from_json(&json).unwrap();
+        assert_eq!(named.find("pump-1".to_string()).unwrap().path, "/pump");
+
+        let dir = std::env::temp_dir();
+        let csv_path = dir.join(format!("registry-{}.csv", std::process::id()));
+        let json_path = dir.join(format!("registry-{}.json", std::process::id()));
+        tree.save_csv(&csv_path).unwrap();
+        tree.save_json(&json_path).unwrap();
+        let from_csv = DeviceRegistry::<usize, String>::load_csv(&csv_path).unwrap();
+        let from_json = DeviceRegistry::<usize, String>::load_json(&json_path).unwrap();
+        std::fs::remove_file(csv_path).unwrap();
+        std::fs::remove_file(json_path).unwrap();
+        assert_eq!(devices(&from_csv), devices(&tree));
+        assert_eq!(devices(&from_json), devices(&tree));
+        assert!(DeviceRegistry::<usize, String>::load_csv(dir.join("missing.csv")).is_err());
+    }
+
+    #[test]
+    fn bst_persistence_loads_balanced() {
+        // Files saved from other tools need not be in id order
+        let count = 20_000;
+        let mut csv = String::from("id,address,path\n");
+        for id in (0..count).rev() {
+            csv.push_str(&format!("{},addr,path\n", id));
+        }
+        let registry = DeviceRegistry::<usize, String>::from_csv(&csv).unwrap();
+        assert_eq!(registry.length, count);
+        assert!(registry.height() <= 15);
+        assert_eq!(registry.find(0).map(|d| d.id), Some(0));
+
+        let json = registry.to_json();
+        let registry = DeviceRegistry::<usize, String>::from_json(&json).unwrap();
+        assert_eq!(registry.length, count);
+        assert!(registry.height() <= 15);
+    }
+
+    #[test]
+    fn bst_persistence_malformed() {
+        assert!(DeviceRegistry::<usize, String>::from_csv("id,address,path\n1,a,b\n").is_ok());
+        assert!(DeviceRegistry::<usize, String>::from_json("[]").is_ok());
+
+        // Each input with the line its error is reported on
+        let csv = [
+            ("", 1),
+            ("id,path\n", 1),
+            ("id,address,path\n1,a,b\n2,a\n", 3),
+            ("id,address,path\n1,\"a\nb\",c\nx,a,b\n", 4),
+            ("id,address,path\n1,a,b\n1,c,d\n", 3),
+            ("id,address,path\n1,a,b\n2,a,b\n2,c,d\n1,c,d\n", 4),
+            ("id,address,path\n\n1,\"a,b\n", 3),
+        ];
+        for (input, expected) in csv {
+            match DeviceRegistry::<usize, String>::from_csv(input) {
+                Err(PersistError::Malformed { line, .. }) => {
+                    assert_eq!(line, expected, "{:?}", input)
+                }
+                _ => panic!("{:?} should be malformed", input),
+            }
+        }
+
+        let json = [
+            ("", 1),
+            (
+                "[\n  {\"id\": 1, \"address\": \"a\", \"path\": \"b\"},\n  {\"id\": 2}\n]",
+                3,
+            ),
+            (
+                "[\n\n  {\"id\": \"x\", \"address\": \"a\", \"path\": \"b\"}\n]",
+                3,
+            ),
+            ("[{\"id\": 1, \"address\": 2, \"path\": \"b\"}]", 1),
+            ("[\n{\"id\": 1,\n\"name\": \"a\"}]", 3),
+            ("[{\"id\": 1, \"address\": \"a\", \"path\": \"b\"}]\n,", 2),
+            (
+                "[{\"id\": 1, \"address\": \"\\ud800\", \"path\": \"b\"}]",
+                1,
+            ),
+        ];
+        for (input, expected) in json {
+            match DeviceRegistry::<usize, String>::from_json(input) {
+                Err(PersistError::Malformed { line, .. }) => {
+                    assert_eq!(line, expected, "{:?}", input)
+                }
+                _ => panic!("{:?} should be malformed", input),
+            }
+        }
+    }
+
+    // Radix Trie
+    #[test]
+    fn rt() {
+        let mut registry = DeviceRegistry::<usize, &str>::new_empty();
+        registry.add(IoTDevice::new(1, "10.1.0.1", "/one"));
+        registry.add(IoTDevice::new(2, "10.1.200.7", "/two"));
+        registry.add(IoTDevice::new(3, "10.2.0.1", "/three"));
+        registry.add(IoTDevice::new(4, "192.168.0.1", "/four"));
+        registry.add(IoTDevice::new(5, "fe80::1", "/five"));
+        registry.add(IoTDevice::new(6, "10.1.0.1", "/six"));
+
+        let mut trie = DeviceTrie::from_registry(&registry).unwrap();
+        assert_eq!(trie.length, 6);
+
+        let ids = |devs: Vec<&IoTDevice<usize, &str>>| -> Vec<usize> {
+            let mut ids: Vec<usize> = devs.iter().map(|d| d.id).collect();
+            ids.sort();
+            ids
+        };
+        let ip = |s: &str| -> IpAddr { s.parse().unwrap() };
+
+        assert_eq!(ids(trie.subnet(ip("10.1.0.0"), 16)), vec![1, 2, 6]);
+        assert_eq!(ids(trie.subnet(ip("10.0.0.0"), 8)), vec![1, 2, 3, 6]);
+        assert_eq!(ids(trie.subnet(ip("0.0.0.0"), 0)), vec![1, 2, 3, 4, 6]);
+        assert_eq!(ids(trie.subnet(ip("10.3.0.0"), 16)), vec![]);
+        assert_eq!(ids(trie.subnet(ip("fe80::"), 10)), vec![5]);
+        assert_eq!(ids(trie.find(ip("10.1.0.1"))), vec![1, 6]);
+        assert_eq!(ids(trie.find(ip("10.1.0.2"))), vec![]);
+
+        let (len, devs) = trie.longest_prefix_match(ip("10.1.0.1")).unwrap();
+        assert_eq!((len, ids(devs)), (32, vec![1, 6]));
+        let (len, devs) = trie.longest_prefix_match(ip("10.2.0.9")).unwrap();
+        assert_eq!((len, ids(devs)), (28, vec![3]));
+        let (len, devs) = trie.longest_prefix_match(ip("10.1.128.0")).unwrap();
+        assert_eq!((len, ids(devs)), (17, vec![2]));
+        assert!(trie.longest_prefix_match(ip("::1")).is_some());
+
+        assert_eq!(trie.remove(ip("10.1.0.1"), 1).map(|d| d.id), Some(1));
+        assert_eq!(trie.remove(ip("10.1.0.1"), 1), None);
+        assert_eq!(trie.remove(ip("10.2.0.1"), 3).map(|d| d.id), Some(3));
+        assert_eq!(trie.length, 4);
+        assert_eq!(ids(trie.subnet(ip("10.0.0.0"), 8)), vec![2, 6]);
+        let (len, devs) = trie.longest_prefix_match(ip("10.2.0.9")).unwrap();
+        assert_eq!((len, ids(devs)), (14, vec![2, 6]));
+
+        let replaced = trie.insert(IoTDevice::new(6, "10.1.0.1", "/new six"));
+        assert_eq!(replaced, Ok(Some(IoTDevice::new(6, "10.1.0.1", "/six"))));
+        let rejected = trie.insert(IoTDevice::new(7, "not an ip", "/seven"));
+        assert!(matches!(rejected, Err(DeviceError::InvalidAddress(_, _))));
+        assert_eq!(trie.length, 4);
+    }
+
+    // Red Black Tree
+    #[test]
+    fn rbt() {
+        let mut tree = BetterDeviceRegistry::<usize, &str>::new_empty();
+        assert_eq!(tree.find(10), None);
+        assert_eq!(tree.validate(), Ok(1));
+
+        tree.add(IoTDevice::new(1, "One Add", "One Path"));
+        tree.add(IoTDevice::new(2, "Two Add", "Two Path"));
+        tree.add(IoTDevice::new(3, "Three Add", "Three Path"));
+
+        assert_eq!(tree.find(2), Some(IoTDevice::new(2, "Two Add", "Two Path")));
+        assert_eq!(
+            tree.find(3),
+            Some(IoTDevice::new(3, "Three Add", "Three Path"))
+        );
+        assert_eq!(tree.find(4), None);
+        assert_eq!(tree.validate(), Ok(3));
+
+        // Like the plain registry, a taken id is replaced, not duplicated
+        assert_eq!(
+            tree.add(IoTDevice::new(2, "New Add", "New Path")),
+            Some(IoTDevice::new(2, "Two Add", "Two Path"))
+        );
+        assert_eq!(tree.find(2), Some(IoTDevice::new(2, "New Add", "New Path")));
+        assert_eq!(tree.length, 3);
+        assert_eq!(tree.validate(), Ok(3));
+    }
+
+    #[test]
+    fn rbt_sorted_input_stays_balanced() {
+        let len = 1024;
+        let mut tree = BetterDeviceRegistry::<usize, String>::new_empty();
+        for id in 0..len {
+            tree.add(new_device_with_id(id));
+            assert!(tree.validate().is_ok());
+        }
+        assert_eq!(tree.length, len);
+
+        // No red node has a red child, so at most every other node on a path
+        // is red and the longest path is at most twice the black height
+        let black_height = tree.validate().unwrap();
+        let height = tree.height();
+        assert!(height <= 2 * black_height);
+        assert!(height as f64 <= 2.0 * ((len + 1) as f64).log2());
+
+        let v: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+        tree.walk(|n| v.borrow_mut().push(n.id));
+        assert_eq!(v.into_inner(), (0..len).collect::<Vec<usize>>());
+    }
+
+    // B-Tree
+    #[test]
+    fn bt() {
+        let mut tree = DeviceDatabase::<usize, &str>::new_empty(3);
+        assert_eq!(tree.find(10), None);
+        assert_eq!(tree.remove(10), None);
+
+        tree.add(IoTDevice::new(1, "One Add", "One Path"));
+        tree.add(IoTDevice::new(2, "Two Add", "Two Path"));
+        tree.add(IoTDevice::new(3, "Three Add", "Three Path"));
+
+        assert_eq!(tree.find(2), Some(IoTDevice::new(2, "Two Add", "Two Path")));
+        assert_eq!(
+            tree.add(IoTDevice::new(3, "New Add", "New Path")),
+            Some(IoTDevice::new(3, "Three Add", "Three Path"))
+        );
+        assert_eq!(tree.find(3), Some(IoTDevice::new(3, "New Add", "New Path")));
+        assert_eq!(tree.length, 3);
+
+        assert_eq!(
+            tree.remove(1),
+            Some(IoTDevice::new(1, "One Add", "One Path"))
+        );
+        assert_eq!(tree.find(1), None);
+        assert_eq!(tree.length, 2);
+    }
+
+    #[test]
+    fn bt_shuffled_add_remove() {
+        let len = 500;
+        let mut rng = rand::thread_rng();
+
+        for order in [3, 4, 5, 16] {
+            let mut tree = DeviceDatabase::<usize, String>::new_empty(order);
+            let mut ids: Vec<usize> = (0..len).collect();
+            ids.shuffle(&mut rng);
+            for id in ids.iter() {
+                assert_eq!(tree.add(new_device_with_id(*id)), None);
+            }
+            assert_eq!(tree.length, len);
+
+            let v: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+            tree.walk(|n| v.borrow_mut().push(n.id));
+            assert_eq!(v.into_inner(), (0..len).collect::<Vec<usize>>());
+
+            ids.shuffle(&mut rng);
+            let (removed, kept) = ids.split_at(len / 2);
+            for id in removed {
+                assert_eq!(tree.remove(*id).map(|d| d.id), Some(*id));
+                assert_eq!(tree.remove(*id), None);
+            }
+            assert_eq!(tree.length, len - removed.len());
+            for id in kept {
+                assert_eq!(tree.find(*id).map(|d| d.id), Some(*id));
+            }
+
+            let mut kept = kept.to_vec();
+            kept.sort();
+            let in_range: Vec<usize> = tree.range(100..=200).iter().map(|d| d.id).collect();
+            let expected: Vec<usize> = kept
+                .iter()
+                .copied()
+                .filter(|id| (100..=200).contains(id))
+                .collect();
+            assert_eq!(in_range, expected);
+            assert_eq!(tree.range(..).len(), kept.len());
+
+            for id in kept.iter() {
+                assert!(tree.remove(*id).is_some());
+            }
+            assert_eq!(tree.length, 0);
+            assert_eq!(tree.range(..).len(), 0);
+        }
+    }
+
+    // Collection traits
+    fn check_queue<Q: Queue<Item = usize> + Default>() {
+        let mut queue = Q::default();
+        assert!(queue.is_empty());
+        for i in 0..5 {
+            queue.push(i);
+        }
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.get(3), Some(3));
+        assert_eq!(queue.get(5), None);
+        assert_eq!(queue.pop_front(), Some(0));
+        assert_eq!(queue.len(), 4);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop_front(), None);
+        queue.push(7);
+        assert_eq!(queue.get(0), Some(7));
+    }
+
+    // `value(key, round)` makes a different value for every round
+    fn check_map<M: OrderedMap<Key = usize> + Default>(value: impl Fn(usize, usize) -> M::Value)
+    where
+        M::Value: PartialEq + Debug,
+    {
+        let mut map = M::default();
+        let mut keys: Vec<usize> = (0..50).collect();
+        keys.shuffle(&mut rand::thread_rng());
+        for key in keys {
+            assert_eq!(map.insert(key, value(key, 0)), None);
+        }
+        assert_eq!(map.len(), 50);
+        assert_eq!(map.keys(), (0..50).collect::<Vec<_>>());
+        assert_eq!(map.get(&7), Some(value(7, 0)));
+        assert_eq!(map.get(&50), None);
+        assert_eq!(map.insert(7, value(7, 1)), Some(value(7, 0)));
+        assert_eq!(map.get(&7), Some(value(7, 1)));
+        assert_eq!(map.len(), 50);
+        map.clear();
+        assert!(map.is_empty());
+        assert!(map.keys().is_empty());
+        assert_eq!(map.get(&7), None);
+    }
+
+    #[test]
+    fn collection_traits() {
+        check_queue::<TransactionLog<usize>>();
+        check_queue::<BetterTransactionLog<usize>>();
+        check_queue::<ArenaTransactionLog<usize>>();
+        check_map::<BestTransactionLog<usize, usize>>(|k, round| k * 10 + round);
+        let device = |k, round: usize| IoTDevice::new(k, round.to_string(), "Path");
+        check_map::<DeviceRegistry<usize, String>>(device);
+        check_map::<DeviceDatabase<usize, String>>(device);
+        let mut registry = DeviceRegistry::<usize, String>::new_empty();
+        assert_eq!(OrderedMap::insert(&mut registry, 5, device(9, 0)), None);
+        assert_eq!(OrderedMap::get(&registry, &5).map(|d| d.id), Some(5));
+        assert_eq!(OrderedMap::get(&registry, &9), None);
+
+        let mut saver: TimestampSaver<usize> = (0..20).collect();
+        assert_eq!(Sequence::get(&saver, 19), Some(19));
+        saver.clear();
+        assert_eq!(saver.len(), 0);
+        assert_eq!(Sequence::get(&saver, 0), None);
+        saver.push(3);
+        assert_eq!(Sequence::get(&saver, 0), Some(3));
+
+        // Indexes survive a clear, empty
+        let mut registry: DeviceRegistry<usize, String> = (0..10).map(new_device_with_id).collect();
+        let address = "Address is 1".to_string();
+        registry.enable_indexes();
+        registry.clear();
+        assert!(registry.find_by_address(&address).is_empty());
+        registry.add(new_device_with_id(1));
+        assert_eq!(registry.find_by_address(&address).len(), 1);
+
+        let mut rbt: BetterDeviceRegistry<usize, String> =
+            (0..10).map(new_device_with_id).collect();
+        assert_eq!(rbt.len(), 10);
+        rbt.clear();
+        assert!(rbt.is_empty());
+        assert_eq!(rbt.find(3), None);
+    }
+
+    // Public API
+    #[test]
+    fn public_api() {
+        use crate::{data_structures::singly_linked_list, prelude::*};
+
+        let mut sll: singly_linked_list::TransactionLog<u8> = (1..=3).collect();
+        sll.extend([4]);
+        assert_eq!(format!("{:?}", sll), "[1, 2, 3, 4]");
+        let mut dll: BetterTransactionLog<u8> = (1..=3).collect();
+        dll.extend([4]);
+        assert_eq!(format!("{:?}", dll), "[1, 2, 3, 4]");
+        assert_eq!(dll.pop_bwd(), Some(4));
+        let da: TimestampSaver<u8> = (1..=20).collect();
+        assert_eq!(da.length, 20);
+        assert_eq!(format!("{:?}", TimestampSaver::<u8>::default()), "[]");
+        let sl: BestTransactionLog<u8, char> = [(1, 'a'), (2, 'b')].into_iter().collect();
+        assert_eq!(sl.find(2), Some('b'));
+
+        let devices = || [3, 1, 2].map(new_device_with_id::<usize, String>);
+        let registry: DeviceRegistry<usize, String> = devices().into_iter().collect();
+        let rbt: BetterDeviceRegistry<usize, String> = devices().into_iter().collect();
+        let mut database: DeviceDatabase<usize, String> = devices().into_iter().collect();
+        database.extend([new_device_with_id(4)]);
+        assert_eq!(registry.length, 3);
+        assert!(registry.is_balanced());
+        assert_eq!(rbt.length, 3);
+        let descending: DeviceRegistry<usize, String> = (0..10_000)
+            .rev()
+            .map(new_device_with_id)
+            .chain([IoTDevice::new(7, "Later Add", "Later Path")])
+            .collect();
+        assert_eq!(descending.length, 10_000);
+        assert!(descending.is_balanced());
+        assert_eq!(
+            descending.find(7).map(|d| d.address),
+            Some("Later Add".to_string())
+        );
+        assert_eq!(database.length, 4);
+        // Debug lists the devices in ascending id order
+        let sorted = |len: usize| (1..=len).map(new_device_with_id).collect::<Vec<_>>();
+        assert!(registry.iter().rev().eq(sorted(3).iter()));
+        assert_eq!(database.range(..), sorted(4));
+        assert_eq!(format!("{:?}", registry), format!("{:?}", sorted(3)));
+        assert_eq!(format!("{:?}", rbt), format!("{:?}", sorted(3)));
+        assert_eq!(format!("{:?}", database), format!("{:?}", sorted(4)));
+        assert_eq!(
+            format!("{:?}", DeviceRegistry::<usize, String>::default()),
+            "[]"
+        );
+
+        let trie: DeviceTrie<usize, String> = [("10.0.0.2", 1), ("::1", 2), ("10.0.0.1", 3)]
+            .into_iter()
+            .map(|(address, id)| TypedIoTDevice::new(id, address, "/", DeviceKind::Sensor).unwrap())
+            .collect();
+        assert_eq!(trie.length, 3);
+        let v4 = trie.subnet(IpAddr::from([10, 0, 0, 0]), 8);
+        assert_eq!(v4.iter().map(|d| d.id).collect::<Vec<_>>(), vec![3, 1]);
+        // Debug lists IPv4 before IPv6, each in address order
+        let by_address = [(3, "10.0.0.1"), (1, "10.0.0.2"), (2, "::1")]
+            .map(|(id, address)| IoTDevice::<usize, String>::new(id, address, "/"));
+        assert_eq!(format!("{:?}", trie), format!("{:?}", by_address));
+    }
 }
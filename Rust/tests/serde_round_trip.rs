@@ -0,0 +1,67 @@
+// Serde support is checked from outside the crate, so the `PartialEq`
+// impls serde_json adds for numbers stay out of the unit tests' type
+// inference.
+#![cfg(feature = "serde")]
+
+use Rust::prelude::*;
+
+#[test]
+fn serde_round_trip() {
+    let mut sll = TransactionLog::new_empty();
+    let mut dll = BetterTransactionLog::new_empty();
+    let mut sl = BestTransactionLog::new_empty(3);
+    let mut da = TimestampSaver::new_empty();
+    for i in 1..=10u64 {
+        sll.append(i);
+        dll.append_bwd(i);
+        sl.append(i, i * 10);
+        da.append(i);
+    }
+    let seq = "[1,2,3,4,5,6,7,8,9,10]";
+    assert_eq!(serde_json::to_string(&sll).unwrap(), seq);
+    assert_eq!(serde_json::to_string(&dll).unwrap(), seq);
+    assert_eq!(serde_json::to_string(&da).unwrap(), seq);
+    let arena: ArenaTransactionLog<u64> = (1..=10).collect();
+    assert_eq!(serde_json::to_string(&arena).unwrap(), seq);
+
+    let mut sll: TransactionLog<u64> = serde_json::from_str(seq).unwrap();
+    assert_eq!(sll.length, 10);
+    assert_eq!(sll.pop(), Some(1));
+    let mut dll: BetterTransactionLog<u64> = serde_json::from_str(seq).unwrap();
+    assert_eq!(dll.pop_fwd(), Some(1));
+    assert_eq!(dll.pop_bwd(), Some(10));
+    let mut arena: ArenaTransactionLog<u64> = serde_json::from_str(seq).unwrap();
+    assert_eq!(arena.pop_bwd(), Some(10));
+    assert_eq!(arena.length, 9);
+    let mut da: TimestampSaver<u64> = serde_json::from_str(seq).unwrap();
+    assert_eq!(da.length, 10);
+    assert_eq!(da.at(9), Some(10));
+
+    let map = serde_json::to_string(&sl).unwrap();
+    assert!(map.starts_with("{\"1\":10,\"2\":20,"));
+    let sl: BestTransactionLog<u64, u64> = serde_json::from_str(&map).unwrap();
+    assert_eq!(sl.length, 10);
+    assert_eq!(sl.find(7), Some(70));
+    assert!(serde_json::from_str::<BestTransactionLog<u64, u64>>("{\"2\":1,\"1\":1}").is_err());
+
+    let mut registry = DeviceRegistry::<usize, String>::new_empty();
+    for i in [3, 1, 2] {
+        registry.add(IoTDevice::new(i, format!("Address is {}", i), "Path"));
+    }
+    let json = serde_json::to_string(&registry).unwrap();
+    assert!(json.starts_with("[{\"id\":1,\"address\":\"Address is 1\""));
+    let registry: DeviceRegistry<usize, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(registry.length, 3);
+    assert_eq!(
+        registry.find(2),
+        Some(IoTDevice::new(2, "Address is 2", "Path"))
+    );
+
+    // Unsorted input with a repeated id keeps the last device
+    let json = r#"[{"id":2,"address":"a","path":"b"},{"id":1,"address":"c","path":"d"},
+                   {"id":2,"address":"e","path":"f"}]"#;
+    let registry: DeviceRegistry<usize, String> = serde_json::from_str(json).unwrap();
+    assert_eq!(registry.length, 2);
+    assert_eq!(registry.find(2), Some(IoTDevice::new(2, "e", "f")));
+    assert!(registry.is_balanced());
+}